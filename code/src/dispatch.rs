@@ -0,0 +1,217 @@
+//! Calling functions with `#[fn_codegen(dispatch)]` by name, with JSON arguments and results.
+use crate::tags::TagSelection;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+
+/// The result of calling a function through a [Dispatcher].
+pub type DispatchFuture = Pin<Box<dyn Future<Output = Result<Value, DispatchError>> + Send>>;
+
+/// A function registered with `#[fn_codegen(dispatch)]`.
+pub struct DispatchFn {
+    /// The Rust name of the function
+    pub name: &'static str,
+    /// e.g. `my_crate::api` from `module_path!()`
+    pub module_path: &'static str,
+    pub tags: &'static [&'static str],
+    /// Deserializes the arguments, calls the function (awaiting it if async) and serializes the result
+    pub invoke: fn(Value) -> DispatchFuture,
+}
+
+#[linkme::distributed_slice]
+pub static CODEGEN_DISPATCH: [DispatchFn] = [..];
+
+/// Functions with `#[fn_codegen(dispatch)]`, looked up by name, for serving them over any transport.
+///
+/// Arguments are either an object with the parameter names as keys, or an array in parameter order.
+/// Missing arguments are `null`, for `Option` parameters.
+///
+/// Functions with the same name in different modules must be called with their module path,
+/// like `my_crate::api::greet`, and calling them by name alone is a [DispatchError::Ambiguous].
+pub struct Dispatcher {
+    /// By name, with more than one for names which are registered in several modules
+    functions: BTreeMap<&'static str, Vec<&'static DispatchFn>>,
+}
+
+impl Dispatcher {
+    /// Functions selected by any of the tag expressions, like for [crate::get_types_by_tags].
+    ///
+    /// Panics for a malformed expression, see [Dispatcher::for_selection] for parsing them separately.
+    #[track_caller]
+    pub fn for_tags(tags: &[&str]) -> Self {
+        let selection = TagSelection::parse(tags).unwrap_or_else(|err| panic!("{err}"));
+        Self::for_selection(&selection)
+    }
+
+    pub fn for_selection(selection: &TagSelection) -> Self {
+        let mut functions = BTreeMap::<&'static str, Vec<&'static DispatchFn>>::new();
+        for function in CODEGEN_DISPATCH
+            .iter()
+            .filter(|function| selection.matches(function.tags))
+        {
+            functions.entry(function.name).or_default().push(function);
+        }
+        for same_name in functions.values_mut() {
+            same_name.sort_by_key(|function| function.module_path);
+        }
+        Dispatcher { functions }
+    }
+
+    /// Names of the functions which can be called, in order,
+    /// with their module paths for names which are registered in several modules.
+    pub fn names(&self) -> impl Iterator<Item = String> + '_ {
+        self.functions
+            .values()
+            .flat_map(|same_name| match same_name.as_slice() {
+                [function] => vec![function.name.to_string()],
+                _ => same_name
+                    .iter()
+                    .map(|function| qualified_name(function))
+                    .collect(),
+            })
+    }
+
+    pub fn call(&self, name: &str, args: Value) -> DispatchFuture {
+        match self.find(name) {
+            Ok(function) => (function.invoke)(args),
+            Err(err) => Box::pin(async move { Err(err) }),
+        }
+    }
+
+    /// The function for a name, or for a path like `my_crate::api::greet`
+    fn find(&self, name: &str) -> Result<&'static DispatchFn, DispatchError> {
+        let not_found = || DispatchError::NotFound(name.to_string());
+        match name.rsplit_once("::") {
+            Some((module_path, fn_name)) => self
+                .functions
+                .get(fn_name)
+                .and_then(|same_name| {
+                    same_name
+                        .iter()
+                        .find(|function| function.module_path == module_path)
+                })
+                .copied()
+                .ok_or_else(not_found),
+            None => match self.functions.get(name).map(Vec::as_slice) {
+                Some([function]) => Ok(function),
+                Some(same_name) => Err(DispatchError::Ambiguous {
+                    name: name.to_string(),
+                    paths: same_name
+                        .iter()
+                        .map(|function| qualified_name(function))
+                        .collect(),
+                }),
+                None => Err(not_found()),
+            },
+        }
+    }
+}
+
+fn qualified_name(function: &DispatchFn) -> String {
+    format!("{}::{}", function.module_path, function.name)
+}
+
+#[derive(Debug)]
+pub enum DispatchError {
+    /// No function of this name was registered with `#[fn_codegen(dispatch)]`
+    NotFound(String),
+    /// Functions of this name were registered in more than one module, so it must be called
+    /// with one of their `paths`
+    Ambiguous { name: String, paths: Vec<String> },
+    /// The arguments weren't an object or array of the parameters, or one of them failed to deserialize
+    InvalidArgs(String),
+    /// The function returned an `Err`, serialized, or as its `Display` text
+    /// for `result_alias`es without an error type
+    Failed(Value),
+    /// The return value failed to serialize
+    Serialize(serde_json::Error),
+}
+
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DispatchError::NotFound(name) => write!(f, "no function named `{name}` to dispatch"),
+            DispatchError::Ambiguous { name, paths } => write!(
+                f,
+                "more than one function named `{name}` to dispatch, call one of `{}`",
+                paths.join("`, `")
+            ),
+            DispatchError::InvalidArgs(message) => write!(f, "invalid arguments: {message}"),
+            DispatchError::Failed(err) => write!(f, "function failed: {err}"),
+            DispatchError::Serialize(err) => write!(f, "failed to serialize the result: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DispatchError {}
+
+/// Arguments of a dispatched call, used by the shims generated for `#[fn_codegen(dispatch)]`.
+#[doc(hidden)]
+pub struct Args {
+    names: &'static [&'static str],
+    values: Vec<Value>,
+}
+
+impl Args {
+    pub fn new(args: Value, names: &'static [&'static str]) -> Result<Self, DispatchError> {
+        let values = match args {
+            Value::Null => vec![Value::Null; names.len()],
+            Value::Array(mut values) if values.len() <= names.len() => {
+                values.resize(names.len(), Value::Null);
+                values
+            }
+            Value::Array(values) => {
+                return Err(DispatchError::InvalidArgs(format!(
+                    "expected at most {} arguments, got {}",
+                    names.len(),
+                    values.len()
+                )))
+            }
+            Value::Object(mut object) => {
+                let values = names
+                    .iter()
+                    .map(|name| object.remove(*name).unwrap_or(Value::Null))
+                    .collect();
+                if let Some(unknown) = object.keys().next() {
+                    return Err(DispatchError::InvalidArgs(format!(
+                        "unknown argument `{unknown}`"
+                    )));
+                }
+                values
+            }
+            other => {
+                return Err(DispatchError::InvalidArgs(format!(
+                    "expected an object or array of arguments, got {other}"
+                )))
+            }
+        };
+        Ok(Args { names, values })
+    }
+
+    pub fn take<T: DeserializeOwned>(&mut self, idx: usize) -> Result<T, DispatchError> {
+        serde_json::from_value(self.values[idx].take()).map_err(|err| {
+            DispatchError::InvalidArgs(format!("argument `{}`: {err}", self.names[idx]))
+        })
+    }
+}
+
+#[doc(hidden)]
+pub fn to_output<T: Serialize>(value: &T) -> Result<Value, DispatchError> {
+    serde_json::to_value(value).map_err(DispatchError::Serialize)
+}
+
+#[doc(hidden)]
+pub fn to_failure<E: Serialize>(err: &E) -> DispatchError {
+    match serde_json::to_value(err) {
+        Ok(err) => DispatchError::Failed(err),
+        Err(err) => DispatchError::Serialize(err),
+    }
+}
+
+#[doc(hidden)]
+pub fn to_failure_message<E: fmt::Display>(err: &E) -> DispatchError {
+    DispatchError::Failed(Value::String(err.to_string()))
+}
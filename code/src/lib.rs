@@ -1,17 +1,39 @@
 pub use linkme;
-use std::collections::BTreeSet;
 
 #[cfg(feature = "experimental")]
 use serde_reflection;
 
+pub mod dispatch;
+pub mod selection;
+pub mod tags;
 pub mod types;
 pub mod utils;
 
+#[doc(hidden)]
+pub use serde_json;
+
+/// Values of a type root which can only be evaluated where the item is declared,
+/// see [Context::add_type_root_with].
+#[derive(Default)]
+pub struct RuntimeValues<'a> {
+    /// Docs from expressions like `#[doc = include_str!("README.md")]`,
+    /// see [utils::doc_include_placeholder].
+    pub doc_includes: &'a [&'a str],
+    /// Serializes the default values for `#[codegen(capture_default)]`,
+    /// see [types::TypeRoot::captured_defaults].
+    #[allow(clippy::type_complexity)]
+    pub captured_defaults: &'a [&'a dyn Fn() -> serde_json::Result<serde_json::Value>],
+    /// Serializes the value of a constant, see [types::TypeRoot::value].
+    pub value: Option<&'a dyn Fn() -> serde_json::Result<serde_json::Value>>,
+}
+
 pub struct Context {
-    tags: BTreeSet<String>,
+    selection: selection::Selection,
     #[cfg(feature = "experimental")]
     tracer: Option<(Vec<(String, types::TypeRoot)>, serde_reflection::Tracer)>,
     untraced: Vec<types::TypeRoot>,
+    /// Items which aren't selected, when they are kept, see [get_types_and_unselected_by_selection]
+    unselected: Option<Vec<types::TypeRoot>>,
     errors: Vec<String>,
 }
 
@@ -22,61 +44,175 @@ impl Context {
         names_json: &str,
         file_name: &str,
         line: u32,
+        module_path: &str,
         tags: &[&str],
-    ) -> () {
-        if !self.should_include(tags) {
+    ) {
+        let Some((type_root, selected)) =
+            self.create_type_root(names_json, file_name, line, module_path, tags)
+        else {
             return;
-        }
-        let type_root = self.create_type_root(names_json, file_name, line);
-        self.untraced.push(type_root);
+        };
+        self.push_type_root(type_root, selected);
     }
 
-    fn should_include(&self, tags: &[&str]) -> bool {
-        if tags.is_empty() {
-            if !self.tags.is_empty() {
-                return false;
-            }
-        } else {
-            let mut found = false;
-            for tag in tags {
-                if self.tags.contains(*tag) {
-                    found = true;
-                    continue;
+    /// Like [Context::add_type_root], but with values which are evaluated where the item is declared.
+    pub fn add_type_root_with(
+        &mut self,
+        names_json: &str,
+        file_name: &str,
+        line: u32,
+        module_path: &str,
+        tags: &[&str],
+        values: RuntimeValues,
+    ) {
+        let Some((mut type_root, selected)) =
+            self.create_type_root(names_json, file_name, line, module_path, tags)
+        else {
+            return;
+        };
+        type_root.doc_includes = values
+            .doc_includes
+            .iter()
+            .map(|doc| doc.to_string())
+            .collect();
+        type_root.captured_defaults = values
+            .captured_defaults
+            .iter()
+            .map(|capture| match capture() {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    self.errors.push(format!(
+                        "Failed to serialize a default value of {} ({file_name}:{line}): {err}",
+                        type_root.inner.rust_ident.value
+                    ));
+                    None
                 }
+            })
+            .collect();
+        type_root.value = values.value.and_then(|value| match value() {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.errors.push(format!(
+                    "Failed to serialize the value of {} ({file_name}:{line}): {err}",
+                    type_root.inner.rust_ident.value
+                ));
+                None
             }
-            if !found {
-                return false;
-            }
-        }
-        return true;
+        });
+        self.push_type_root(type_root, selected);
     }
 
+    /// The type root, with whether it's in the [selection::Selection].
+    /// `None` if it isn't, unless unselected items are kept.
     fn create_type_root(
         &mut self,
         names_json: &str,
         file_name: &str,
         line: u32,
-    ) -> types::TypeRoot {
+        module_path: &str,
+        tags: &[&str],
+    ) -> Option<(types::TypeRoot, bool)> {
         let mut type_root = serde_json::from_str::<types::TypeRoot>(names_json)
             .expect("Incompatible versions of generate & code");
+        let selected = self
+            .selection
+            .matches(tags, module_path, &type_root.inner.rust_ident.value);
+        if !selected && self.unselected.is_none() {
+            return None;
+        }
 
         type_root.file = file_name.to_string();
         type_root.line = line;
-        type_root
+        type_root.module_path = module_path.to_string();
+        self.exclude_members(&mut type_root);
+        warn_ambiguous_repr(&mut type_root);
+        Some((type_root, selected))
+    }
+
+    fn push_type_root(&mut self, type_root: types::TypeRoot, selected: bool) {
+        if selected {
+            self.untraced.push(type_root);
+        } else if let Some(unselected) = &mut self.unselected {
+            unselected.push(type_root);
+        }
+    }
+
+    /// Removes fields and variants whose `#[codegen(tags = "...")]` aren't selected.
+    /// Fields and variants without tags are always kept, as are members of tuples,
+    /// whose positions can't change.
+    fn exclude_members(&mut self, type_root: &mut types::TypeRoot) {
+        use types::{ContainerFormat, RootItem, VariantFormat};
+        let root = &mut type_root.inner;
+        let container_default = root.has_serde_default();
+        let RootItem::Container(ref mut container) = root.value else {
+            return;
+        };
+        let location = format!("{}:{}", type_root.file, type_root.line);
+        let root_ident = &root.rust_ident.value;
+        let warnings = &mut type_root.warnings;
+        match container {
+            ContainerFormat::Struct(fields) => {
+                self.exclude_fields(fields, container_default, root_ident, &location, warnings);
+            }
+            ContainerFormat::Enum(variants) => {
+                variants.retain(|_, variant| self.is_member_included(variant));
+                for variant in variants.values_mut() {
+                    if let VariantFormat::Struct(fields) = &mut variant.value {
+                        let ident = format!("{root_ident}::{}", variant.rust_ident.value);
+                        self.exclude_fields(fields, false, &ident, &location, warnings);
+                    }
+                }
+            }
+            _ => {}
+        }
     }
 
-    #[cfg(features = "experimental")]
+    /// Excluded fields which aren't `Option`s or defaulted are required to deserialize
+    /// the container, so they are added to its [types::TypeRoot::warnings].
+    fn exclude_fields(
+        &self,
+        fields: &mut Vec<types::Named<types::Format>>,
+        container_default: bool,
+        ident: &str,
+        location: &str,
+        warnings: &mut Vec<String>,
+    ) {
+        fields.retain(|field| {
+            if self.is_member_included(field) {
+                return true;
+            }
+            let optional = matches!(field.value, types::Format::Option(_));
+            if !optional && !container_default && !field.has_serde_default() {
+                warnings.push(format!(
+                    "`{ident}.{}` is excluded by its tags {:?}, but is required to deserialize `{ident}` \
+                    as it is neither an `Option` nor `#[serde(default)]` ({location})",
+                    field.rust_ident.value,
+                    field.codegen_tags(),
+                ));
+            }
+            false
+        });
+    }
+
+    fn is_member_included<T>(&self, member: &types::Named<T>) -> bool {
+        let tags = member.codegen_tags();
+        tags.is_empty() || self.selection.tags.matches(&tags)
+    }
+
+    #[cfg(feature = "experimental")]
     pub fn trace_type_root<T>(
         &mut self,
         names_json: &str,
         file_name: &str,
         line: u32,
+        module_path: &str,
         tags: &[&str],
     ) -> () {
-        if !self.should_include(tags) {
+        let Some((type_root, true)) =
+            self.create_type_root(names_json, file_name, line, module_path, tags)
+        else {
             return;
-        }
-        let type_root = self.create_type_root(names_json, file_name, line);
+        };
 
         match &type_root.inner.value {
             types::ContainerFormat::Enum(_) => {
@@ -115,13 +251,80 @@ impl Context {
     }
 }
 
+/// Unit-only enums with an integer `#[repr]` are usually serialized as numbers by `serde_repr`,
+/// but `Serialize_repr` is only visible to `#[derive(Codegen)]` in a `#[derive]` below it.
+/// Without a visible serde derive or `#[codegen(serde_repr)]`, such an enum is described by its
+/// variant names, which is added to its [types::TypeRoot::warnings] in case it's wrong.
+fn warn_ambiguous_repr(type_root: &mut types::TypeRoot) {
+    use types::{ContainerFormat, RootItem, VariantFormat};
+    let root = &type_root.inner;
+    let RootItem::Container(ContainerFormat::Enum(variants)) = &root.value else {
+        return;
+    };
+    let Some(repr) = root
+        .rust_attrs
+        .repr
+        .iter()
+        .find(|repr| types::INTEGER_REPRS.contains(&repr.value.as_str()))
+    else {
+        return;
+    };
+    let unit_only = variants
+        .values()
+        .all(|variant| matches!(variant.value, VariantFormat::Unit));
+    let serde_known = root
+        .codegen_flags
+        .iter()
+        .any(|flag| flag.value == "serde_repr")
+        || root.rust_attrs.derives.iter().any(|derive| {
+            matches!(
+                derive.value.as_str(),
+                "Serialize" | "Deserialize" | "Serialize_repr" | "Deserialize_repr"
+            )
+        });
+    if variants.is_empty() || !unit_only || serde_known {
+        return;
+    }
+    type_root.warnings.push(format!(
+        "`{}` has `#[repr({})]` but no serde derive is visible to `#[derive(Codegen)]`, so it's \
+        described by its variant names. Add `#[codegen(serde_repr)]` if it's serialized as numbers \
+        by `serde_repr` ({}:{})",
+        root.rust_ident.value, repr.value, type_root.file, type_root.line,
+    ));
+}
+
 #[linkme::distributed_slice]
 pub static CODEGEN_ITEMS: [fn(&mut Context)] = [..];
 
+/// Items selected by any of the tag expressions, see [tags::TagExpr].
+///
+/// Panics for a malformed expression, see [get_types_by_selection] for parsing them separately.
 #[track_caller]
 pub fn get_types_by_tags(tags: &[String]) -> Vec<types::TypeRoot> {
+    let selection = tags::TagSelection::parse(tags).unwrap_or_else(|err| panic!("{err}"));
+    get_types_by_selection(&selection.into())
+}
+
+pub fn get_types_by_selection(selection: &selection::Selection) -> Vec<types::TypeRoot> {
+    collect_types(selection, false).0
+}
+
+/// Like [get_types_by_selection], along with the items which aren't selected, from the same pass
+/// over [CODEGEN_ITEMS]. Their fields and variants are included by the tags of the selection too,
+/// e.g. for adding the items which the selected ones reference.
+pub fn get_types_and_unselected_by_selection(
+    selection: &selection::Selection,
+) -> (Vec<types::TypeRoot>, Vec<types::TypeRoot>) {
+    collect_types(selection, true)
+}
+
+fn collect_types(
+    selection: &selection::Selection,
+    keep_unselected: bool,
+) -> (Vec<types::TypeRoot>, Vec<types::TypeRoot>) {
     let mut context = Context {
-        tags: tags.into_iter().cloned().map(String::from).collect(),
+        selection: selection.clone(),
+        unselected: keep_unselected.then(Vec::new),
         errors: Vec::new(),
         #[cfg(feature = "experimental")]
         tracer: None,
@@ -129,9 +332,9 @@ pub fn get_types_by_tags(tags: &[String]) -> Vec<types::TypeRoot> {
         untraced: Vec::new(),
     };
     {
-        let mut context = &mut context;
+        let context = &mut context;
         for gen in CODEGEN_ITEMS {
-            gen(&mut context);
+            gen(context);
             if !context.errors.is_empty() {
                 for err in &context.errors {
                     eprintln!("{err}");
@@ -145,13 +348,14 @@ pub fn get_types_by_tags(tags: &[String]) -> Vec<types::TypeRoot> {
     let Context {
         errors,
         untraced: mut type_roots,
+        unselected,
         #[cfg(feature = "experimental")]
         tracer,
-        tags,
+        selection,
     } = context;
 
     if !errors.is_empty() {
-        eprintln!("Context trace errors for tags {tags:?}:");
+        eprintln!("Context trace errors for tags {}:", selection.tags);
         for err in errors {
             eprintln!(" * {err:?}");
         }
@@ -191,8 +395,8 @@ pub fn get_types_by_tags(tags: &[String]) -> Vec<types::TypeRoot> {
                                 format.replace_incomplete(format_to_format(&reflected));
                             },
                             (VariantFormat::Tuple(ref mut formats), sr::VariantFormat::Tuple(reflected_formats)) => {
-                                for (format, reflected) in formats.iter_mut().zip(reflected_formats.iter()) {
-                                    format.replace_incomplete(format_to_format(&reflected));
+                                for (named_format, reflected) in formats.iter_mut().zip(reflected_formats.iter()) {
+                                    named_format.value.replace_incomplete(format_to_format(&reflected));
                                 }
                             },
                             (VariantFormat::Struct(ref mut named_formats), sr::VariantFormat::Struct(reflected_named_formats)) => {
@@ -222,8 +426,8 @@ pub fn get_types_by_tags(tags: &[String]) -> Vec<types::TypeRoot> {
                     format.replace_incomplete(format_to_format(&reflected_format));
                 },
                 (ContainerFormat::TupleStruct(formats), sr::ContainerFormat::TupleStruct(reflected_formats)) => {
-                    for (ref mut format, reflected_format) in formats.iter_mut().zip(reflected_formats.iter()) {
-                        format.replace_incomplete(format_to_format(&reflected_format));
+                    for (ref mut named_format, reflected_format) in formats.iter_mut().zip(reflected_formats.iter()) {
+                        named_format.value.replace_incomplete(format_to_format(&reflected_format));
                     }
                 },
                 (named_type, reflected_type) => {
@@ -235,7 +439,7 @@ pub fn get_types_by_tags(tags: &[String]) -> Vec<types::TypeRoot> {
         }));
     }
 
-    type_roots
+    (type_roots, unselected.unwrap_or_default())
 }
 
 #[cfg(feature = "experimental")]
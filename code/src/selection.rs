@@ -0,0 +1,76 @@
+//! Selecting the items of a generation by tags, crate, module path or id.
+use crate::tags::TagSelection;
+
+/// Items matching the tag expressions or any of the inclusions, leaving out those matching an exclusion.
+///
+/// When there are inclusions but no tag expressions, only the inclusions are selected,
+/// rather than every item without tags.
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    pub tags: TagSelection,
+    pub include: Vec<ItemFilter>,
+    pub exclude: Vec<ItemFilter>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemFilter {
+    /// Items declared in the crate, e.g. `"billing"`. Dashes are the same as underscores.
+    Crate(String),
+    /// Items declared in the module or its submodules, starting with the crate,
+    /// e.g. `"billing::invoices"` as given by `module_path!()`.
+    Module(String),
+    /// Items with the id, e.g. `"Invoice"`, or with their module path, e.g. `"billing::invoices::Invoice"`
+    Id(String),
+}
+
+impl ItemFilter {
+    pub fn matches(&self, module_path: &str, id: &str) -> bool {
+        match self {
+            ItemFilter::Crate(name) => {
+                let crate_name = module_path.split("::").next().unwrap_or_default();
+                crate_name == name.replace('-', "_")
+            }
+            ItemFilter::Module(module) => in_module(module_path, module),
+            ItemFilter::Id(filter_id) => match filter_id.rsplit_once("::") {
+                Some((module, filter_id)) => module == module_path && filter_id == id,
+                None => filter_id == id,
+            },
+        }
+    }
+}
+
+impl Selection {
+    /// Whether an item with `tags`, declared in the `module_path`, is selected
+    pub fn matches(&self, tags: &[&str], module_path: &str, id: &str) -> bool {
+        let by_tags = if self.tags.is_empty() && !self.include.is_empty() {
+            false
+        } else {
+            self.tags.matches(tags)
+        };
+        (by_tags
+            || self
+                .include
+                .iter()
+                .any(|filter| filter.matches(module_path, id)))
+            && !self
+                .exclude
+                .iter()
+                .any(|filter| filter.matches(module_path, id))
+    }
+}
+
+impl From<TagSelection> for Selection {
+    fn from(tags: TagSelection) -> Self {
+        Selection {
+            tags,
+            ..Selection::default()
+        }
+    }
+}
+
+/// Whether `module_path` is `module` or one of its submodules
+fn in_module(module_path: &str, module: &str) -> bool {
+    module_path
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
@@ -0,0 +1,195 @@
+//! Selecting items by their tags, with expressions like `api & !internal`, `api | admin` or `api-*`.
+use std::fmt;
+
+/// A parsed tag expression.
+///
+/// Tags can contain `*` wildcards, like `api-*`, and `*` alone matches every item, including those without tags.
+/// `!` binds tighter than `&`, which binds tighter than `|`, and parentheses can group expressions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TagExpr {
+    /// A tag, or a pattern like `api-*`
+    Tag(String),
+    Not(Box<TagExpr>),
+    And(Box<TagExpr>, Box<TagExpr>),
+    Or(Box<TagExpr>, Box<TagExpr>),
+}
+
+impl TagExpr {
+    pub fn parse(expr: &str) -> Result<Self, TagExprError> {
+        let mut parser = Parser { expr, pos: 0 };
+        let parsed = parser.or()?;
+        parser.skip_whitespace();
+        if parser.pos < expr.len() {
+            return Err(parser.error("expected `&`, `|` or the end of the expression"));
+        }
+        Ok(parsed)
+    }
+
+    /// Whether an item with `tags` is selected
+    pub fn matches(&self, tags: &[&str]) -> bool {
+        match self {
+            TagExpr::Tag(pattern) if pattern == "*" => true,
+            TagExpr::Tag(pattern) => tags.iter().any(|tag| glob_matches(pattern, tag)),
+            TagExpr::Not(inner) => !inner.matches(tags),
+            TagExpr::And(left, right) => left.matches(tags) && right.matches(tags),
+            TagExpr::Or(left, right) => left.matches(tags) || right.matches(tags),
+        }
+    }
+}
+
+/// The tag expressions of a generation, of which an item has to match any.
+#[derive(Clone, Debug, Default)]
+pub struct TagSelection {
+    /// The expressions as written, and parsed
+    exprs: Vec<(String, TagExpr)>,
+}
+
+impl TagSelection {
+    pub fn parse<S: AsRef<str>>(exprs: &[S]) -> Result<Self, TagExprError> {
+        let mut selection = TagSelection::default();
+        for expr in exprs {
+            selection.push(expr.as_ref())?;
+        }
+        Ok(selection)
+    }
+
+    pub fn push(&mut self, expr: &str) -> Result<(), TagExprError> {
+        self.exprs.push((expr.to_string(), TagExpr::parse(expr)?));
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exprs.is_empty()
+    }
+
+    /// Whether an item with `tags` is selected by any of the expressions.
+    /// Without expressions, only items without tags are selected.
+    pub fn matches(&self, tags: &[&str]) -> bool {
+        if self.exprs.is_empty() {
+            return tags.is_empty();
+        }
+        self.exprs.iter().any(|(_, expr)| expr.matches(tags))
+    }
+}
+
+impl fmt::Display for TagSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (expr, _)) in self.exprs.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "`{expr}`")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagExprError {
+    pub expr: String,
+    /// Byte offset of the error in [TagExprError::expr]
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for TagExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid tag expression `{}`: {} at offset {}",
+            self.expr, self.message, self.position
+        )
+    }
+}
+
+impl std::error::Error for TagExprError {}
+
+/// Whether `tag` matches a `pattern` with `*` wildcards
+fn glob_matches(pattern: &str, tag: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == tag,
+        Some((prefix, rest)) => {
+            let Some(after) = tag.strip_prefix(prefix) else {
+                return false;
+            };
+            after
+                .char_indices()
+                .map(|(idx, _)| idx)
+                .chain([after.len()])
+                .any(|idx| glob_matches(rest, &after[idx..]))
+        }
+    }
+}
+
+struct Parser<'a> {
+    expr: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn or(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut left = self.and()?;
+        while self.eat('|') {
+            left = TagExpr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<TagExpr, TagExprError> {
+        let mut left = self.unary()?;
+        while self.eat('&') {
+            left = TagExpr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<TagExpr, TagExprError> {
+        if self.eat('!') {
+            Ok(TagExpr::Not(Box::new(self.unary()?)))
+        } else if self.eat('(') {
+            let inner = self.or()?;
+            if !self.eat(')') {
+                return Err(self.error("expected `)`"));
+            }
+            Ok(inner)
+        } else {
+            self.tag()
+        }
+    }
+
+    fn tag(&mut self) -> Result<TagExpr, TagExprError> {
+        self.skip_whitespace();
+        let rest = &self.expr[self.pos..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "&|!()".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected a tag"));
+        }
+        self.pos += len;
+        Ok(TagExpr::Tag(rest[..len].to_string()))
+    }
+
+    fn eat(&mut self, token: char) -> bool {
+        self.skip_whitespace();
+        if self.expr[self.pos..].starts_with(token) {
+            self.pos += token.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.expr[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn error(&self, message: &'static str) -> TagExprError {
+        TagExprError {
+            expr: self.expr.to_string(),
+            position: self.pos,
+            message,
+        }
+    }
+}
@@ -8,21 +8,55 @@ pub struct TypeRoot {
     pub file: String,
     #[serde(rename = "l")]
     pub line: u32,
+    /// e.g. `"billing::invoices"`, from `module_path!()` where the item is declared
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    #[serde(rename = "m")]
+    pub module_path: String,
     #[serde(rename = "i")]
     pub inner: Named<RootItem>,
     /// e.g. built-in types
     #[serde(rename = "e")]
     pub extras: Vec<Named<ContainerFormat>>,
+    /// Docs from expressions like `#[doc = include_str!("README.md")]`, which are evaluated
+    /// where the item is declared. See [crate::utils::doc_include_placeholder].
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "di")]
+    pub doc_includes: Vec<String>,
+    /// Serialized default values for `#[codegen(capture_default)]`, which are evaluated
+    /// where the item is declared. `None` where the value failed to serialize.
+    /// See [Named::captured_default].
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "cd")]
+    pub captured_defaults: Vec<Option<serde_json::Value>>,
+    /// The serialized value of a constant, which is evaluated at registration.
+    /// `None` for other items, or where the value failed to serialize.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "v")]
+    pub value: Option<serde_json::Value>,
+    /// Problems with the item in its selection, such as fields which are excluded by their tags
+    /// but are required to deserialize it.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "w")]
+    pub warnings: Vec<String>,
 }
 
-/// Containers (structs and enums) or functions (fns with `#[fn_codegen]`).
+/// Containers (structs and enums), functions (fns with `#[fn_codegen]`),
+/// constants (`const` and `static` items with `#[const_codegen]`),
+/// aliases (`type` items with `#[codegen]`), services (`impl` blocks with `#[codegen]`)
+/// or interfaces (`trait`s with `#[codegen]`).
 #[derive(Serialize, Deserialize, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum RootItem {
     Container(ContainerFormat),
     Function(FunctionFormat),
+    Constant(ConstantFormat),
+    /// The aliased type, e.g. `Paginated<T>` for `type Page<T> = Paginated<T, 50>;`
+    Alias(Format),
+    Service(ServiceFormat),
+    Interface(InterfaceFormat),
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Spanned<T> {
     #[serde(rename = "$")]
     pub value: T,
@@ -56,6 +90,11 @@ pub enum Format {
         ident: String,
         generics: Vec<Format>,
     },
+    /// A type parameter of a function, `impl` block or trait, e.g. `T` in `fn list<T>() -> Vec<T>`.
+    /// Type parameters of containers are left as [Format::TypeName]s.
+    Generic {
+        ident: String,
+    },
 
     // The formats of primitive types
     Unit,
@@ -128,7 +167,7 @@ impl Format {
     }
     pub fn is_typename(&self) -> Option<(&str, &[Format])> {
         match self {
-            Format::TypeName { ident, generics } => Some((&ident, &generics)),
+            Format::TypeName { ident, generics } => Some((ident, generics)),
             _ => None,
         }
     }
@@ -137,7 +176,7 @@ impl Format {
             Format::Incomplete { debug } => todo!("Unknown ident incomplete: {debug}"),
             Format::TypeName { ident, generics } => {
                 return Cow::Owned({
-                    let mut buf = format!("{ident}");
+                    let mut buf = ident.to_string();
                     for gen in generics.iter() {
                         buf.push('_');
                         buf.push_str(&gen.as_ident());
@@ -145,6 +184,7 @@ impl Format {
                     buf
                 })
             }
+            Format::Generic { ident } => return Cow::Owned(ident.to_string()),
             Format::Unit => "Nil",
             Format::Bool => "Bool",
             Format::I8 => "I8",
@@ -243,7 +283,8 @@ pub enum ContainerFormat {
     /// A struct with a single unnamed parameter, e.g. `struct A(u16)`
     NewTypeStruct(Box<Format>),
     /// A struct with several unnamed parameters, e.g. `struct A(u16, u32)`
-    TupleStruct(Vec<Format>),
+    /// Members are named by their position, e.g. `"0"` and `"1"`.
+    TupleStruct(Vec<Named<Format>>),
     /// A struct with named parameters, e.g. `struct A { a: Foo }`.
     Struct(Vec<Named<Format>>),
     /// An enum, that is, an enumeration of variants.
@@ -259,7 +300,8 @@ pub enum VariantFormat {
     /// A variant with a single unnamed parameter, e.g. `A` in `enum X { A(u16) }`
     NewType(Box<Format>),
     /// A struct with several unnamed parameters, e.g. `A` in `enum X { A(u16, u32) }`
-    Tuple(Vec<Format>),
+    /// Members are named by their position, e.g. `"0"` and `"1"`.
+    Tuple(Vec<Named<Format>>),
     /// A struct with named parameters, e.g. `A` in `enum X { A { a: Foo } }`
     Struct(Vec<Named<Format>>),
 }
@@ -269,8 +311,155 @@ pub enum VariantFormat {
 pub struct FunctionFormat {
     pub is_async: bool,
     pub self_opt: Option<Named<Format>>,
+    /// How `self` is taken, when there is a [FunctionFormat::self_opt]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub receiver: Option<ReceiverKind>,
     pub params: Vec<Named<Format>>,
     pub ret: Box<Format>,
+    /// The output of a returned future, e.g. `User` for `impl Future<Output = User>` or `BoxFuture<'static, User>`.
+    /// The return type of an `async fn` is already its output.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ret_future_output: Option<Box<Format>>,
+    /// The success type when [FunctionFormat::ret] is a `Result`, e.g. `User` for `Result<User, ApiError>`,
+    /// or for a `Result` alias from `#[codegen(result_alias = "...")]`, e.g. `User` for `io::Result<User>`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ret_ok: Option<Box<Format>>,
+    /// The error type when [FunctionFormat::ret] is a `Result`, e.g. `ApiError` for `Result<User, ApiError>`.
+    /// `None` for `Result` aliases which weren't given an error type.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ret_err: Option<Box<Format>>,
+    /// Bounds of type parameters, from both the parameter list and the where clause,
+    /// e.g. `("T", ["Resource", "Clone"])` for `fn list<T: Resource>() where T: Clone`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub bounds: Vec<Spanned<(String, Vec<String>)>>,
+    /// e.g. `["'a"]` for `fn first<'a>(items: &'a [Item]) -> &'a Item`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub lifetimes: Vec<Spanned<String>>,
+    /// Where clause predicates which aren't bounds of a type parameter, e.g. `Vec<T>: Debug`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub where_predicates: Vec<Spanned<String>>,
+    /// Whether requests or responses are streamed, from the parameters and (future) output
+    #[serde(skip_serializing_if = "CallKind::is_unary", default)]
+    pub call: CallKind,
+    /// Parameters which are web framework extractors like `Json(body): Json<CreateUser>`,
+    /// whose formats in [FunctionFormat::params] are of their contents
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub extractors: Vec<Extractor>,
+    /// From `#[fn_codegen(method = "POST", path = "/users/{id}")]` or a framework attribute like `#[post("/users/{id}")]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub route: Option<Route>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Route {
+    /// Upper case, e.g. `"POST"`
+    pub method: Spanned<String>,
+    /// With `{name}` placeholders, e.g. `"/users/{id}"`, also for `/users/:id` or `/users/<id>`
+    pub path: Spanned<String>,
+    /// One for each placeholder of [Route::path], in order
+    pub path_params: Vec<PathParam>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PathParam {
+    /// e.g. `"id"` for `{id}`
+    pub name: String,
+    /// The index in [FunctionFormat::params]
+    pub param: usize,
+    /// The index in a tuple like `Path((org, team)): Path<(u64, u64)>`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub field: Option<usize>,
+    /// The name the tuple field is bound to, e.g. `"team"` for `Path((org, team))`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub binding: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Extractor {
+    /// The index in [FunctionFormat::params]
+    pub param: usize,
+    /// e.g. `"Json"` for `Json<CreateUser>`
+    pub extractor: Spanned<String>,
+    pub source: ParamSource,
+}
+
+/// Where an [Extractor] takes its parameter from in a request
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum ParamSource {
+    /// e.g. `Json<T>` or `Form<T>`
+    Body,
+    /// e.g. `Path<T>`
+    Path,
+    /// e.g. `Query<T>`
+    Query,
+    /// e.g. `TypedHeader<T>`
+    Header,
+    /// Server-only state which isn't part of the request, e.g. `State<T>` or `Extension<T>`
+    State,
+}
+
+/// How a function is called, see [FunctionFormat::call].
+/// Streams are recognized as `impl Stream<Item = T>`, `Pin<Box<dyn Stream<Item = T>>>`,
+/// `BoxStream<'_, T>`, or channel receivers like `mpsc::Receiver<T>`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub enum CallKind {
+    /// One request and one response, e.g. `fn get(id: u32) -> User`
+    #[default]
+    Unary,
+    /// A stream of responses, e.g. `fn watch() -> impl Stream<Item = Event>`
+    ServerStreaming { item: Format },
+    /// A stream of requests from the `param`, e.g. `fn upload(chunks: BoxStream<'static, Chunk>) -> u64`
+    ClientStreaming { param: String, item: Format },
+    /// Streams of both requests and responses
+    Bidirectional {
+        param: String,
+        request: Format,
+        response: Format,
+    },
+}
+
+impl CallKind {
+    pub fn is_unary(&self) -> bool {
+        matches!(self, CallKind::Unary)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum ReceiverKind {
+    /// `self` or `mut self`
+    Owned,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+    /// e.g. `self: Box<Self>` or `self: Pin<&mut Self>`
+    Typed,
+}
+
+/// The method signatures of a `trait`, where `Self` is left as the implementing type.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InterfaceFormat {
+    pub methods: Vec<Named<FunctionFormat>>,
+}
+
+/// The methods of an `impl` block, with `Self` substituted by the implementing type.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ServiceFormat {
+    /// e.g. `UserService` for `impl UserService`
+    pub self_type: Format,
+    /// e.g. `Handler` for `impl Handler for UserService`
+    pub trait_type: Option<Format>,
+    /// Public methods, and other methods with a `#[codegen]` attribute.
+    /// Every method of trait impls.
+    pub methods: Vec<Named<FunctionFormat>>,
+}
+
+/// The type of a `const` or `static` item, whose value is in [TypeRoot::value].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConstantFormat {
+    /// `true` for `static` items, `false` for `const` items
+    pub is_static: bool,
+    pub format: Format,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -290,16 +479,163 @@ pub struct Named<T> {
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     #[serde(rename = "sf")]
     pub serde_flags: Vec<Spanned<String>>,
+    /// Every `#[serde(...)]` item, including nested forms like `rename(serialize = "a")`
+    /// which aren't in [Named::serde_attrs] or [Named::serde_flags].
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "sm")]
+    pub serde_meta: Vec<AttrMeta>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     #[serde(rename = "ca")]
     pub codegen_attrs: Vec<Spanned<(Spanned<String>, Spanned<String>)>>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     #[serde(rename = "cf")]
     pub codegen_flags: Vec<Spanned<String>>,
+    /// Items of `#[codegen(...)]` within scopes, like `ts(as = "Date")` or
+    /// `when(tag = "admin", rename = "x")`, which only apply to some generations.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "cs")]
+    pub codegen_scoped: Vec<Spanned<ScopedCodegenAttr>>,
+    #[serde(skip_serializing_if = "RustAttrs::is_empty", default)]
+    #[serde(rename = "ra")]
+    pub rust_attrs: RustAttrs,
+    /// Index into [TypeRoot::captured_defaults] for containers and defaulted fields
+    /// with `#[codegen(capture_default)]` on their container.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "cd")]
+    pub captured_default: Option<usize>,
     #[serde(rename = "$")]
     pub value: T,
 }
 
+/// An attribute or flag in scopes of `#[codegen(...)]`, e.g. `as = "Date"` in `ts(as = "Date")`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ScopedCodegenAttr {
+    /// From the outermost, e.g. `[Target("ts"), Tags(["admin"])]` for `ts(when(tag = "admin", as = "Date"))`
+    #[serde(rename = "s")]
+    pub scopes: Vec<CodegenScope>,
+    #[serde(rename = "k")]
+    pub key: Spanned<String>,
+    /// `None` for flags
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "v")]
+    pub value: Option<Spanned<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum CodegenScope {
+    /// A generator the items are meant for, e.g. `"ts"` for `ts(as = "Date")`
+    Target(String),
+    /// Tags of which one must be selected, e.g. `["admin"]` for `when(tag = "admin", ...)`
+    Tags(Vec<String>),
+}
+
+/// An item of an attribute like `#[serde(...)]`, e.g. `flatten`, `rename = "a"` or `rename(serialize = "a")`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttrMeta {
+    /// e.g. `"rename"`
+    #[serde(rename = "k")]
+    pub key: Spanned<String>,
+    #[serde(rename = "v")]
+    pub value: AttrMetaValue,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum AttrMetaValue {
+    /// e.g. `flatten`
+    Flag,
+    /// e.g. `"a"` for `rename = "a"`. Literals other than strings are kept as written.
+    Lit(Spanned<String>),
+    /// e.g. `serialize = "a", deserialize = "b"` for `rename(serialize = "a", deserialize = "b")`
+    List(Vec<AttrMeta>),
+}
+
+/// The `#[repr(...)]`s of enums whose discriminants are integers, as used by `serde_repr`
+pub const INTEGER_REPRS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+/// Rust language attributes and properties of an item which are not specific to serde or codegen.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RustAttrs {
+    /// e.g. `["u8"]` for `#[repr(u8)]`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "r")]
+    pub repr: Vec<Spanned<String>>,
+    /// Other derive macros visible to the codegen derive, e.g. `["Serialize_repr"]`.
+    /// Derives listed in the same `#[derive(...)]` as `Codegen` are not visible.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "d")]
+    pub derives: Vec<Spanned<String>>,
+    /// Only specified for enum variants, e.g. `1` for `A = 1`.
+    /// `None` if the discriminant could not be evaluated.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "dc")]
+    pub discriminant: Option<Spanned<i128>>,
+    /// e.g. `#[deprecated(since = "1.2.0", note = "Use `other` instead")]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "dp")]
+    pub deprecated: Option<Spanned<Deprecated>>,
+    /// `#[non_exhaustive]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "ne")]
+    pub non_exhaustive: Option<Spanned<()>>,
+    /// `#[doc(hidden)]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "dh")]
+    pub doc_hidden: Option<Spanned<()>>,
+    /// `#[must_use]` or `#[must_use = "reason"]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "mu")]
+    pub must_use: Option<Spanned<Option<String>>>,
+    /// e.g. `"pub"`, `"pub(crate)"`, or `""` for private items.
+    /// Only specified for containers, fields and functions.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "v")]
+    pub visibility: Option<Spanned<String>>,
+    /// e.g. `["feature = \"admin\""]` for `#[cfg(feature = "admin")]`
+    ///
+    /// Item level `#[cfg]`s are evaluated and removed by the compiler before macros run, even when
+    /// they're below an attribute macro, so they're only found on fields, variants and function
+    /// parameters. Items can give theirs with `#[codegen(cfg = "feature = \"admin\"")]`.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "c")]
+    pub cfg: Vec<Spanned<String>>,
+    /// e.g. `("unix", ["allow(dead_code)"])` for `#[cfg_attr(unix, allow(dead_code))]`
+    ///
+    /// Derive macros only see the expanded attributes, so these are only found on function parameters.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    #[serde(rename = "ca")]
+    pub cfg_attr: Vec<Spanned<(String, Vec<String>)>>,
+}
+
+impl RustAttrs {
+    pub fn is_empty(&self) -> bool {
+        self.repr.is_empty()
+            && self.derives.is_empty()
+            && self.discriminant.is_none()
+            && self.deprecated.is_none()
+            && self.non_exhaustive.is_none()
+            && self.doc_hidden.is_none()
+            && self.must_use.is_none()
+            && self.visibility.is_none()
+            && self.cfg.is_empty()
+            && self.cfg_attr.is_empty()
+    }
+}
+
+/// The arguments of `#[deprecated]`, all of which are optional.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Deprecated {
+    /// e.g. `"1.2.0"` for `#[deprecated(since = "1.2.0")]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "s")]
+    pub since: Option<Spanned<String>>,
+    /// e.g. `"Use `other` instead"` for `#[deprecated = "Use `other` instead"]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[serde(rename = "n")]
+    pub note: Option<Spanned<String>>,
+}
+
 impl<T> Named<T> {
     pub fn builtin(ident: &str, docs: &str, bytes: Option<(usize, usize)>, value: T) -> Self {
         Named {
@@ -318,8 +654,12 @@ impl<T> Named<T> {
             },
             serde_attrs: Vec::new(),
             serde_flags: Vec::new(),
+            serde_meta: Vec::new(),
             codegen_attrs: Vec::new(),
             codegen_flags: Vec::new(),
+            codegen_scoped: Vec::new(),
+            rust_attrs: RustAttrs::default(),
+            captured_default: None,
             value,
         }
     }
@@ -351,7 +691,27 @@ impl<T> Named<T> {
                     None
                 }
             })
-            .last()
+            .next_back()
             .unwrap_or(&self.rust_ident.value)
     }
+
+    /// Tags from `#[codegen(tags = "a, b")]`, e.g. `["a", "b"]`.
+    pub fn codegen_tags(&self) -> Vec<&str> {
+        self.codegen_attrs
+            .iter()
+            .filter(|attr| attr.value.0.value == "tags" || attr.value.0.value == "tag")
+            .flat_map(|attr| attr.value.1.value.split(','))
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    /// Whether the item has a serde `#[serde(default)]` or `#[serde(default = "path")]`.
+    pub fn has_serde_default(&self) -> bool {
+        self.serde_flags.iter().any(|flag| flag.value == "default")
+            || self
+                .serde_attrs
+                .iter()
+                .any(|attr| attr.value.0.value == "default")
+    }
 }
@@ -1,3 +1,9 @@
+/// Stands in for the docs from the expression at `index` of [crate::types::TypeRoot::doc_includes]
+/// in `rust_docs`, since those can only be evaluated by the compiler after the derive has run.
+pub fn doc_include_placeholder(index: usize) -> String {
+    format!("\u{1a}doc_include:{index}\u{1a}")
+}
+
 pub fn parse_span<S: std::fmt::Debug + Copy>(
     span: S,
 ) -> std::result::Result<(usize, usize), String> {
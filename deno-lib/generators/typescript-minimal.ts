@@ -129,8 +129,8 @@ export function generateTypeScript(
                 variant,
               )}]);`;
             },
-            Tuple(formats) {
-              const formatTsList = tupleFormats(formats);
+            Tuple({ fields }) {
+              const formatTsList = tupleFormats(fields.map((field) => field.format));
               const vnStr = namedStr(variant);
               const innerTypeRef = `[${formatTsList.map((f) => f.fmt.src).join(", ")}]`;
               // type
@@ -212,8 +212,8 @@ export function generateTypeScript(
         }
         $decl.add`}`;
       },
-      TupleStruct(formats) {
-        const formatTsList = tupleFormats(formats);
+      TupleStruct({ fields }) {
+        const formatTsList = tupleFormats(fields.map((field) => field.format));
         const structIdent = ident(decl.id);
         // type
         $decl.lines.push(...docs);
@@ -327,6 +327,7 @@ const createFormat: (format: gen.Format) => {
     const generics = value.generics.length ? `<${value.generics.map((g) => createFormat(g).src).join(", ")}>` : "";
     return { src: `${ident(value.ident)}${generics}` };
   },
+  Generic: (value) => ({ src: ident(value.ident) }),
   I8: num,
   I16: num,
   I32: num,
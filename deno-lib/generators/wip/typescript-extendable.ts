@@ -238,8 +238,8 @@ defineTypeScriptExtension({
                       variant,
                     )} in input) return to.${variantNameField}(input[${namedStr(variant)}]);`;
                   },
-                  Tuple(formats) {
-                    const formatTsList = tupleFormats(formats);
+                  Tuple({ fields }) {
+                    const formatTsList = tupleFormats(fields.map((field) => field.format));
                     const vnStr = namedStr(variant);
                     const innerTypeRef = `[${formatTsList.map((f) => f.fmt.src).join(", ")}]`;
                     // type
@@ -321,8 +321,8 @@ defineTypeScriptExtension({
               }
               $decl.add`}`;
             },
-            TupleStruct(formats) {
-              const formatTsList = tupleFormats(formats);
+            TupleStruct({ fields }) {
+              const formatTsList = tupleFormats(fields.map((field) => field.format));
               const structIdent = ident(decl.id);
               // type
               $decl.lines.push(...docs);
@@ -476,6 +476,7 @@ const createFormat: (format: gen.Format) => {
     const generics = value.generics.length ? `<${value.generics.map((g) => createFormat(g).src).join(", ")}>` : "";
     return { src: `${ident(value.ident)}${generics}` };
   },
+  Generic: (value) => ({ src: ident(value.ident) }),
   I8: num,
   I16: num,
   I32: num,
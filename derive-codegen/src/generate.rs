@@ -1,3 +1,5 @@
+use i_codegen_code::selection::{ItemFilter, Selection};
+use i_codegen_code::tags::TagSelection;
 use i_codegen_code::types as st;
 use i_codegen_derive::CodegenInternal;
 use rayon::prelude::*;
@@ -7,12 +9,15 @@ use st::TypeRoot;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::Arc;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fmt::Debug,
     io::BufReader,
 };
 
+mod docs;
+
 #[derive(CodegenInternal, Serialize, Deserialize, Clone, Debug)]
 #[serde(transparent)]
 #[codegen(tags = "derive-codegen-internal")]
@@ -23,6 +28,15 @@ struct LocationID(String);
 struct Input {
     declarations: Vec<InputDeclaration>,
     functions: Vec<FunctionDeclaration>,
+    constants: Vec<ConstantDeclaration>,
+    aliases: Vec<AliasDeclaration>,
+    services: Vec<ServiceDeclaration>,
+    interfaces: Vec<InterfaceDeclaration>,
+    operations: Vec<OperationDeclaration>,
+    /// Problems with the selected items, such as fields which are excluded by their tags
+    /// but are required to deserialize their container
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    warnings: Vec<String>,
 }
 
 #[derive(Serialize, Debug, CodegenInternal)]
@@ -34,6 +48,9 @@ struct InputDeclaration {
     #[serde(flatten)]
     attrs: Attrs,
     container_kind: ContainerFormat,
+    /// Not selected by the generation, but referenced by a selected item
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    included_as_dependency: bool,
 }
 
 #[derive(Serialize, Debug, CodegenInternal)]
@@ -47,14 +64,193 @@ struct FunctionDeclaration {
     function: FunctionFormat,
 }
 
+/// A `const` or `static` item with `#[const_codegen]`
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct ConstantDeclaration {
+    id: String,
+    id_location: LocationID,
+    /// Contains docs, and `[codegen]` attr information.
+    #[serde(flatten)]
+    attrs: Attrs,
+    /// `true` for `static` items, `false` for `const` items
+    is_static: bool,
+    format: Format,
+    /// The serialized value, e.g. `100` for `const MAX_ITEMS: usize = 100;`.
+    /// `None` if the value failed to serialize.
+    #[codegen(ts_as = "unknown")]
+    value: Option<serde_json::Value>,
+}
+
+/// A `type` item with `#[codegen]`, e.g. `type UserId = String;`
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct AliasDeclaration {
+    id: String,
+    id_location: LocationID,
+    /// Contains generics, docs, and `[codegen]` attr information.
+    #[serde(flatten)]
+    attrs: Attrs,
+    /// e.g. `Str` for `type UserId = String;`
+    target: Format,
+    /// Not selected by the generation, but referenced by a selected item
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    included_as_dependency: bool,
+}
+
+/// An `impl` block with `#[codegen]`, whose id is the name of the implementing type.
+/// Types with several `impl` blocks have a service for each.
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct ServiceDeclaration {
+    /// e.g. `UserServiceImpl` for `impl UserService`, or `UserServiceHandlerImpl` for `impl Handler for UserService`,
+    /// so that it's distinct from the id of the type's declaration and of its other `impl` blocks.
+    id: String,
+    id_location: LocationID,
+    /// Contains generics of the `impl`, docs, and `[codegen]` attr information.
+    #[serde(flatten)]
+    attrs: Attrs,
+    /// e.g. `UserService` for `impl UserService`, which `Self` has been replaced by in the methods.
+    self_type: Format,
+    /// e.g. `Handler` for `impl Handler for UserService`
+    trait_type: Option<Format>,
+    /// Public methods, and other methods with a `#[codegen]` attribute.
+    /// Every method of trait impls.
+    methods: Vec<FunctionDeclaration>,
+}
+
+/// A `trait` with `#[codegen]`, e.g. for describing the server side of an RPC interface.
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct InterfaceDeclaration {
+    id: String,
+    id_location: LocationID,
+    /// Contains generics, docs, and `[codegen]` attr information.
+    #[serde(flatten)]
+    attrs: Attrs,
+    /// Every method signature, where `Self` is the implementing type.
+    methods: Vec<FunctionDeclaration>,
+}
+
+/// An HTTP operation of a function in `functions`, from `#[fn_codegen(method = "POST", path = "/users/{id}")]`
+/// or a framework attribute like `#[post("/users/{id}")]`.
+///
+/// Parameters are placed by their extractors, see `FunctionParameter::extractor`. Functions without
+/// extractors take their parameters in the query for `GET`, `HEAD`, `DELETE`, `OPTIONS` and `TRACE`,
+/// and in the body otherwise.
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct OperationDeclaration {
+    /// The id of the function in `functions`
+    function_id: String,
+    /// Upper case, e.g. `"POST"`
+    method: String,
+    /// With `{name}` placeholders, e.g. `"/users/{id}"`, also for `/users/:id` or `/users/<id>`
+    path: String,
+    path_location: LocationID,
+    /// One for each placeholder in `path`, in order
+    path_params: Vec<OperationParameter>,
+    query_params: Vec<OperationParameter>,
+    header_params: Vec<OperationParameter>,
+    body_params: Vec<OperationParameter>,
+    /// e.g. `User` for `Result<User, ApiError>`, or the (future's) return type otherwise
+    success: Format,
+    /// e.g. `ApiError` for `Result<User, ApiError>`
+    error: Option<Format>,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct OperationParameter {
+    /// The placeholder for path parameters, e.g. `"id"` for `{id}`, otherwise the id of the function parameter
+    name: String,
+    /// The id of the function parameter, or the name a tuple field is bound to,
+    /// e.g. `"team"` for `Path((org, team)): Path<(u64, u64)>`
+    param: String,
+    /// The index of the function parameter in [FunctionFormat::params]
+    param_index: usize,
+    /// The index in a tuple like `Path((org, team)): Path<(u64, u64)>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    field: Option<usize>,
+    /// e.g. `U64` for `team` in `Path((org, team)): Path<(u64, u64)>`
+    format: Format,
+}
+
 #[derive(Serialize, Debug, CodegenInternal)]
 #[codegen(tags = "derive-codegen-internal")]
 struct FunctionFormat {
     /// Whether this function was declared with async
     is_async: bool,
     self_opt: Option<Box<FunctionParameter>>,
+    /// How `self` is taken, when there is a `self_opt`
+    receiver: Option<ReceiverKind>,
     params: Vec<FunctionParameter>,
     return_type: Box<Format>,
+    /// The output of a returned future, e.g. `User` for `impl Future<Output = User>` or `BoxFuture<'static, User>`.
+    /// The `return_type` of an `async fn` is already its output.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    return_future_output: Option<Box<Format>>,
+    /// The success type when `return_type` is a `Result`, e.g. `User` for `Result<User, ApiError>`.
+    /// Aliases such as `io::Result<User>` are recognized with `#[codegen(result_alias = "io::Result<io::Error>")]`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    return_ok: Option<Box<Format>>,
+    /// The error type when `return_type` is a `Result`, e.g. `ApiError` for `Result<User, ApiError>`.
+    /// `None` for aliases which were configured without an error type, like `result_alias = "anyhow::Result"`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    return_err: Option<Box<Format>>,
+    /// Bounds of the type parameters in `rust_generics`, from both the parameter list and the where clause.
+    /// e.g. `T: Resource + Clone` as `{ ident: "T", bounds: ["Resource", "Clone"] }`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    bounds: Vec<TypeParamBounds>,
+    /// e.g. `["'a"]` for `fn first<'a>(items: &'a [Item]) -> &'a Item`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    lifetimes: Vec<(String, LocationID)>,
+    /// Where clause predicates which aren't bounds of a type parameter, e.g. `Vec<T>: Debug`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    where_predicates: Vec<(String, LocationID)>,
+    /// Whether requests or responses are streamed. Left out for unary functions.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    call: Option<CallKind>,
+}
+
+/// Streams are recognized as `impl Stream<Item = T>`, `Pin<Box<dyn Stream<Item = T>>>`,
+/// `BoxStream<'_, T>`, or channel receivers like `mpsc::Receiver<T>`.
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+enum CallKind {
+    /// A stream of responses, e.g. `fn watch() -> impl Stream<Item = Event>`
+    ServerStreaming { item: Format },
+    /// A stream of requests from the `param`, e.g. `fn upload(chunks: BoxStream<'static, Chunk>) -> u64`
+    ClientStreaming { param: String, item: Format },
+    /// Streams of both requests and responses
+    Bidirectional {
+        param: String,
+        request: Format,
+        response: Format,
+    },
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct TypeParamBounds {
+    /// e.g. `"T"`
+    ident: String,
+    /// e.g. `["Resource", "Clone"]`
+    bounds: Vec<String>,
+    location: LocationID,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+enum ReceiverKind {
+    /// `self` or `mut self`
+    Owned,
+    /// `&self`
+    Ref,
+    /// `&mut self`
+    RefMut,
+    /// e.g. `self: Box<Self>` or `self: Pin<&mut Self>`
+    Typed,
 }
 
 #[derive(Deserialize, Debug, CodegenInternal)]
@@ -85,8 +281,9 @@ struct OutputMessage {
 /// Serde-based serialization format for anonymous "value" types.
 /// This is just the path respecting serde names into the container
 /// It gets replaced by the knowledge
-#[derive(Serialize, Debug, CodegenInternal)]
+#[derive(Serialize, Debug, Clone, CodegenInternal)]
 #[codegen(tags = "derive-codegen-internal")]
+#[allow(clippy::upper_case_acronyms)]
 enum Format {
     Incomplete {
         debug: String,
@@ -96,6 +293,11 @@ enum Format {
         ident: String,
         generics: Vec<Format>,
     },
+    /// A type parameter of a function, `impl` block or trait, e.g. `T` in `fn list<T>() -> Vec<T>`.
+    /// Type parameters of containers are left as `TypeName`s, see their `rust_generics`.
+    Generic {
+        ident: String,
+    },
 
     // The formats of primitive types
     Unit,
@@ -150,7 +352,8 @@ enum ContainerFormat {
     /// A struct with a single unnamed parameter, e.g. `struct A(u16)`
     NewTypeStruct(Box<Format>),
     /// A struct with several unnamed parameters, e.g. `struct A(u16, u32)`
-    TupleStruct(Vec<Format>),
+    /// Each field's `id` is its position, e.g. `"0"`.
+    TupleStruct { fields: Vec<NamedField> },
     /// A struct with named parameters, e.g. `struct A { a: Foo }`.
     Struct { fields: Vec<NamedField> },
     /// An enum, that is, an enumeration of variants.
@@ -166,6 +369,12 @@ enum ContainerFormat {
 struct NamedVariant {
     id: String,
     id_location: LocationID,
+    /// Position of the variant in the enum declaration, starting from `0`.
+    index: u32,
+    /// e.g. `1` for `A = 1`, following Rust's rules for implicit discriminants.
+    /// `None` if the discriminant is an expression which couldn't be evaluated, like a constant.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    discriminant: Option<i128>,
     #[serde(flatten)]
     attrs: Attrs,
     variant_format: VariantFormat,
@@ -188,7 +397,37 @@ struct FunctionParameter {
     id_location: LocationID,
     #[serde(flatten)]
     attrs: Attrs,
+    /// The contents of the `extractor`, if any, e.g. `CreateUser` for `Json<CreateUser>`
     format: Format,
+    /// How a web framework takes this parameter from a request,
+    /// e.g. from the body for `Json(body): Json<CreateUser>`.
+    /// Recognized extractors can be added to with `#[codegen(extractor = "Cbor = body")]`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    extractor: Option<ParamExtractor>,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct ParamExtractor {
+    /// e.g. `"Json"` for `Json<CreateUser>`
+    ident: String,
+    location: LocationID,
+    source: ParamSource,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+enum ParamSource {
+    /// e.g. `Json<T>` or `Form<T>`
+    Body,
+    /// e.g. `Path<T>`
+    Path,
+    /// e.g. `Query<T>`
+    Query,
+    /// e.g. `TypedHeader<T>`
+    Header,
+    /// Server-only state which isn't part of the request, e.g. `State<T>` or `Extension<T>`
+    State,
 }
 
 #[derive(Serialize, Debug, CodegenInternal)]
@@ -200,7 +439,8 @@ enum VariantFormat {
     /// A variant with a single unnamed parameter, e.g. `A` in `enum X { A(u16) }`
     NewType(Box<Format>),
     /// A struct with several unnamed parameters, e.g. `A` in `enum X { A(u16, u32) }`
-    Tuple(Vec<Format>),
+    /// Each field's `id` is its position, e.g. `"0"`.
+    Tuple { fields: Vec<NamedField> },
     /// A struct with named parameters, e.g. `A` in `enum X { A { a: Foo } }`
     Struct { fields: Vec<NamedField> },
 }
@@ -209,26 +449,123 @@ enum VariantFormat {
 #[codegen(tags = "derive-codegen-internal")]
 struct Attrs {
     /// Documentation comments like this one.
-    /// Future idea: Pass in tokens with links to other types.
     rust_docs: Option<String>,
+    /// [Attrs::rust_docs] split into summary, sections, code blocks and links,
+    /// with intra-doc links like `[BasicEnum]` resolved to ids in this generation.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    rust_docs_parsed: Option<docs::Docs>,
     /// Only specified for enums and structs
     /// Future: Consider whether we should monomorphize on the codegen side...
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     rust_generics: Vec<(String, LocationID)>,
     /// e.g. `#[serde(rename = "newName")]`, your generator will need to describe what it supports
     /// Not applicable to derived functions.
+    /// When a key is repeated, this is its last value, and [Attrs::serde_attrs_repeated] has all of them.
     #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
     serde_attrs: BTreeMap<String, (String, LocationID)>,
+    /// Every value of each key in [Attrs::serde_attrs], in order, whether or not the key is repeated.
+    /// e.g. `#[serde(alias = "a", alias = "b")]` as `{ "alias": [["a", ..], ["b", ..]] }`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    serde_attrs_repeated: BTreeMap<String, Vec<(String, LocationID)>>,
     /// e.g. `#[serde(transparent)]`, your generator will need to describe what it supports
     /// Not applicable to derived functions.
     #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
     serde_flags: BTreeMap<String, LocationID>,
+    /// Every item of `#[serde(...)]` attributes in order, as a tree, including nested forms
+    /// like `#[serde(rename(serialize = "a", deserialize = "b"))]` which are left out of
+    /// [Attrs::serde_attrs] and [Attrs::serde_flags].
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    serde_meta: Vec<AttrMeta>,
     /// e.g. `#[codegen(ts_as = "Date")]` - these are customizable for your generator's use cases.
+    /// When a key is repeated, this is its last value, and [Attrs::codegen_attrs_repeated] has all of them.
     #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
     codegen_attrs: BTreeMap<String, (String, LocationID)>,
+    /// Every value of each key in [Attrs::codegen_attrs], in order, whether or not the key is repeated.
+    /// e.g. multiple `#[codegen(tags = "...")]` lines.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
+    codegen_attrs_repeated: BTreeMap<String, Vec<(String, LocationID)>>,
     /// e.g. `#[codegen(hidden)]` - these are customizable for your generator's use cases.
     #[serde(skip_serializing_if = "BTreeMap::is_empty", default)]
     codegen_flags: BTreeMap<String, LocationID>,
+    /// e.g. `#[repr(u8)]` as `["u8"]`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    rust_repr: Vec<(String, LocationID)>,
+    /// Other derive macros visible to `#[derive(Codegen)]`, e.g. `#[derive(Serialize_repr)]`.
+    /// Derives listed in the same `#[derive(...)]` as `Codegen` are not visible to it.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    rust_derives: Vec<(String, LocationID)>,
+    /// e.g. `#[deprecated(since = "1.2.0", note = "Use `other` instead")]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    rust_deprecated: Option<Deprecated>,
+    /// `#[non_exhaustive]` on enums, structs and variants, meaning more variants or fields may be added later.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    rust_non_exhaustive: Option<LocationID>,
+    /// `#[doc(hidden)]`, usually meaning this should be left out of documentation.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    rust_doc_hidden: Option<LocationID>,
+    /// `#[must_use]` or `#[must_use = "reason"]`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    rust_must_use: Option<(Option<String>, LocationID)>,
+    /// e.g. `"pub"`, `"pub(crate)"`, `"pub(in crate::api)"`, or `""` for private items.
+    /// Only specified for containers, fields and functions.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    rust_visibility: Option<(String, LocationID)>,
+    /// e.g. `#[cfg(feature = "admin")]` as `["feature = \"admin\""]`
+    /// Found on fields, variants and function parameters, since the compiler evaluates and removes
+    /// `#[cfg]`s on items before `Codegen` can see them. Items give theirs with `#[codegen(cfg = "...")]`.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    rust_cfg: Vec<(String, LocationID)>,
+    /// e.g. `#[cfg_attr(unix, allow(dead_code))]`
+    /// Only found on function parameters, since derives only see the attributes after expansion.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    rust_cfg_attr: Vec<CfgAttr>,
+    /// The serialized default value, for containers marked `#[codegen(capture_default)]`
+    /// and their fields with `#[serde(default)]` or `#[serde(default = "path")]`.
+    /// e.g. `{ "value": 1 }` from `T::default()` or `1` from the `path()` of a field.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    #[codegen(ts_as = "unknown")]
+    captured_default: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct CfgAttr {
+    /// e.g. `"unix"` for `#[cfg_attr(unix, allow(dead_code))]`
+    predicate: String,
+    /// e.g. `["allow(dead_code)"]` for `#[cfg_attr(unix, allow(dead_code))]`
+    attrs: Vec<String>,
+    location: LocationID,
+}
+
+/// e.g. `rename(serialize = "a")` from `#[serde(rename(serialize = "a"), alias = "b")]`
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct AttrMeta {
+    /// e.g. `"rename"`
+    key: String,
+    key_location: LocationID,
+    value: AttrMetaValue,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+enum AttrMetaValue {
+    /// e.g. `flatten`
+    Flag,
+    /// e.g. `"a"` for `rename = "a"`
+    Lit { value: String, location: LocationID },
+    /// e.g. `serialize = "a", deserialize = "b"` for `rename(serialize = "a", deserialize = "b")`
+    List { items: Vec<AttrMeta> },
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct Deprecated {
+    /// e.g. `"1.2.0"` for `#[deprecated(since = "1.2.0")]`
+    since: Option<String>,
+    /// e.g. `"Use `other` instead"` for `#[deprecated = "Use `other` instead"]`
+    note: Option<String>,
+    location: LocationID,
 }
 
 #[derive(Serialize, Debug, CodegenInternal)]
@@ -247,6 +584,12 @@ enum EnumRepresentation {
         content: Option<String>,
         content_location: Option<LocationID>,
     },
+    /// e.g `1`, for enums deriving `Serialize_repr` from `serde_repr` (or marked `#[codegen(serde_repr)]`)
+    /// See https://serde.rs/enum-number.html
+    Integer {
+        /// e.g. `"u8"` for `#[repr(u8)]`
+        repr: String,
+    },
 }
 
 #[derive(Clone)]
@@ -263,8 +606,7 @@ impl SourceLineNumberIndex {
         let mut newlines = Vec::new();
         let mut is_crlf = false;
 
-        let mut current_byte = 0;
-        for byte_result in BufReader::new(file).bytes() {
+        for (current_byte, byte_result) in BufReader::new(file).bytes().enumerate() {
             match byte_result.expect("read next byte") {
                 b'\n' => {
                     newlines.push(current_byte + 1);
@@ -274,7 +616,6 @@ impl SourceLineNumberIndex {
                 }
                 _ => {}
             }
-            current_byte += 1;
         }
 
         Self { is_crlf, newlines }
@@ -302,6 +643,14 @@ struct TypeRootConverter {
     file_name: String,
     line_number_override: Option<u32>,
     lines: SourceLineNumberIndex,
+    /// See [st::TypeRoot::doc_includes]
+    doc_includes: Vec<String>,
+    /// See [st::TypeRoot::captured_defaults]
+    captured_defaults: Vec<Option<serde_json::Value>>,
+    /// Ids of all declarations and functions in the generation, for resolving doc links
+    ids: Arc<HashSet<String>>,
+    /// For resolving [st::Named::codegen_scoped]
+    selection: Arc<Generation>,
 }
 
 impl TypeRootConverter {
@@ -335,28 +684,141 @@ impl TypeRootConverter {
             )
         }
     }
+    fn attr_meta(&self, st::AttrMeta { key, value }: st::AttrMeta) -> AttrMeta {
+        let (key, key_location) = self.location_id(key);
+        AttrMeta {
+            key,
+            key_location,
+            value: match value {
+                st::AttrMetaValue::Flag => AttrMetaValue::Flag,
+                st::AttrMetaValue::Lit(lit) => {
+                    let (value, location) = self.location_id(lit);
+                    AttrMetaValue::Lit { value, location }
+                }
+                st::AttrMetaValue::List(items) => AttrMetaValue::List {
+                    items: items
+                        .into_iter()
+                        .map(|attr_meta| self.attr_meta(attr_meta))
+                        .collect(),
+                },
+            },
+        }
+    }
+    fn repeated_attrs(
+        &self,
+        attrs: &[st::Spanned<(st::Spanned<String>, st::Spanned<String>)>],
+    ) -> BTreeMap<String, Vec<(String, LocationID)>> {
+        let mut bt = BTreeMap::<String, Vec<(String, LocationID)>>::new();
+        for st::Spanned {
+            value: (key, value),
+            ..
+        } in attrs
+        {
+            bt.entry(key.value.clone())
+                .or_default()
+                .push(self.location_id(value.clone()));
+        }
+        bt
+    }
+    /// The scoped `#[codegen(...)]` attributes and flags which apply to this generation.
+    ///
+    /// `when(tag = "a", ...)` items apply when one of its tags is selected. Target scopes like
+    /// `ts(as = "Date")` are prefixed as `ts_as`, unless the generation selects targets with
+    /// [Generation::include_target], which then only keeps the selected targets' items as `as`.
+    #[allow(clippy::type_complexity)]
+    fn resolve_scoped(
+        &self,
+        scoped: Vec<st::Spanned<st::ScopedCodegenAttr>>,
+    ) -> (
+        Vec<st::Spanned<(st::Spanned<String>, st::Spanned<String>)>>,
+        Vec<st::Spanned<String>>,
+    ) {
+        let mut attrs = Vec::new();
+        let mut flags = Vec::new();
+        'scoped: for st::Spanned {
+            value: st::ScopedCodegenAttr { scopes, key, value },
+            bytes,
+        } in scoped
+        {
+            let mut targets = Vec::new();
+            for scope in scopes {
+                match scope {
+                    st::CodegenScope::Tags(tags) => {
+                        let tags = tags.iter().map(String::as_str).collect::<Vec<_>>();
+                        if !self.selection.items.tags.matches(&tags) {
+                            continue 'scoped;
+                        }
+                    }
+                    st::CodegenScope::Target(target) => targets.push(target),
+                }
+            }
+            let key = if self.selection.targets.is_empty() {
+                targets.push(key.value);
+                st::Spanned {
+                    value: targets.join("_"),
+                    bytes: key.bytes,
+                }
+            } else if targets
+                .iter()
+                .all(|target| self.selection.targets.contains(target))
+            {
+                key
+            } else {
+                continue;
+            };
+            match value {
+                Some(value) => attrs.push(st::Spanned {
+                    value: (key, value),
+                    bytes,
+                }),
+                None => flags.push(key),
+            }
+        }
+        (attrs, flags)
+    }
+    fn fill_doc_includes(&self, mut docs: String) -> String {
+        for (index, include) in self.doc_includes.iter().enumerate() {
+            docs = docs.replace(
+                &i_codegen_code::utils::doc_include_placeholder(index),
+                include.trim(),
+            );
+        }
+        docs
+    }
     fn unname<T>(
         &self,
         st::Named {
-            codegen_attrs,
-            codegen_flags,
+            mut codegen_attrs,
+            mut codegen_flags,
+            codegen_scoped,
             rust_docs,
             rust_ident,
             rust_generics,
             serde_attrs,
             serde_flags,
+            serde_meta,
+            rust_attrs,
+            captured_default,
             value,
         }: st::Named<T>,
     ) -> (st::Spanned<String>, T, Attrs) {
+        let (scoped_attrs, scoped_flags) = self.resolve_scoped(codegen_scoped);
+        codegen_attrs.extend(scoped_attrs);
+        codegen_flags.extend(scoped_flags);
+        let rust_docs = rust_docs.map(|docs| self.fill_doc_includes(docs));
         (
             rust_ident,
             value,
             Attrs {
+                rust_docs_parsed: rust_docs
+                    .as_deref()
+                    .map(|docs| docs::parse_docs(docs, &self.ids)),
                 rust_docs,
                 rust_generics: rust_generics
                     .into_iter()
                     .map(|gen| self.location_id(gen))
                     .collect(),
+                serde_attrs_repeated: self.repeated_attrs(&serde_attrs),
                 serde_attrs: {
                     let mut bt = BTreeMap::<String, (String, LocationID)>::new();
                     for st::Spanned {
@@ -376,6 +838,11 @@ impl TypeRootConverter {
                     }
                     bt
                 },
+                serde_meta: serde_meta
+                    .into_iter()
+                    .map(|attr_meta| self.attr_meta(attr_meta))
+                    .collect(),
+                codegen_attrs_repeated: self.repeated_attrs(&codegen_attrs),
                 codegen_attrs: {
                     let mut bt = BTreeMap::<String, (String, LocationID)>::new();
                     for st::Spanned {
@@ -395,6 +862,55 @@ impl TypeRootConverter {
                     }
                     bt
                 },
+                rust_repr: rust_attrs
+                    .repr
+                    .into_iter()
+                    .map(|repr| self.location_id(repr))
+                    .collect(),
+                rust_derives: rust_attrs
+                    .derives
+                    .into_iter()
+                    .map(|derive| self.location_id(derive))
+                    .collect(),
+                rust_deprecated: rust_attrs.deprecated.map(|deprecated| {
+                    let (st::Deprecated { since, note }, location) = self.location_id(deprecated);
+                    Deprecated {
+                        since: since.map(|since| since.value),
+                        note: note.map(|note| note.value),
+                        location,
+                    }
+                }),
+                rust_non_exhaustive: rust_attrs
+                    .non_exhaustive
+                    .map(|non_exhaustive| self.location_id(non_exhaustive).1),
+                rust_doc_hidden: rust_attrs
+                    .doc_hidden
+                    .map(|doc_hidden| self.location_id(doc_hidden).1),
+                rust_must_use: rust_attrs
+                    .must_use
+                    .map(|must_use| self.location_id(must_use)),
+                rust_visibility: rust_attrs
+                    .visibility
+                    .map(|visibility| self.location_id(visibility)),
+                rust_cfg: rust_attrs
+                    .cfg
+                    .into_iter()
+                    .map(|cfg| self.location_id(cfg))
+                    .collect(),
+                rust_cfg_attr: rust_attrs
+                    .cfg_attr
+                    .into_iter()
+                    .map(|cfg_attr| {
+                        let ((predicate, attrs), location) = self.location_id(cfg_attr);
+                        CfgAttr {
+                            predicate,
+                            attrs,
+                            location,
+                        }
+                    })
+                    .collect(),
+                captured_default: captured_default
+                    .and_then(|index| self.captured_defaults.get(index).cloned().flatten()),
             },
         )
     }
@@ -408,6 +924,7 @@ impl TypeRootConverter {
                     .map(|format| self.format_to_format(format))
                     .collect(),
             },
+            st::Format::Generic { ident } => Format::Generic { ident },
             st::Format::Unit => Format::Unit,
             st::Format::Bool => Format::Bool,
             st::Format::I8 => Format::I8,
@@ -459,17 +976,112 @@ impl TypeRootConverter {
             params: args,
             is_async,
             ret,
+            ret_future_output,
+            ret_ok,
+            ret_err,
+            call,
+            extractors,
+            // see [TypeRootConverter::route_to_operation]
+            route: _,
             self_opt,
+            receiver,
+            bounds,
+            lifetimes,
+            where_predicates,
         } = function_format;
+        let mut extractors = extractors
+            .into_iter()
+            .map(|extractor| (extractor.param, extractor))
+            .collect::<HashMap<_, _>>();
         FunctionFormat {
+            bounds: bounds
+                .into_iter()
+                .map(|bounds| {
+                    let ((ident, bounds), location) = self.location_id(bounds);
+                    TypeParamBounds {
+                        ident,
+                        bounds,
+                        location,
+                    }
+                })
+                .collect(),
+            lifetimes: lifetimes
+                .into_iter()
+                .map(|lifetime| self.location_id(lifetime))
+                .collect(),
+            where_predicates: where_predicates
+                .into_iter()
+                .map(|predicate| self.location_id(predicate))
+                .collect(),
+            receiver: receiver.map(|receiver| match receiver {
+                st::ReceiverKind::Owned => ReceiverKind::Owned,
+                st::ReceiverKind::Ref => ReceiverKind::Ref,
+                st::ReceiverKind::RefMut => ReceiverKind::RefMut,
+                st::ReceiverKind::Typed => ReceiverKind::Typed,
+            }),
             params: args
                 .into_iter()
-                .map(|st_named_format| self.named_format_to_function_parameter(st_named_format))
+                .enumerate()
+                .map(|(idx, st_named_format)| {
+                    let mut param = self.named_format_to_function_parameter(st_named_format);
+                    param.extractor = extractors.remove(&idx).map(|extractor| {
+                        let (ident, location) = self.location_id(extractor.extractor);
+                        ParamExtractor {
+                            ident,
+                            location,
+                            source: match extractor.source {
+                                st::ParamSource::Body => ParamSource::Body,
+                                st::ParamSource::Path => ParamSource::Path,
+                                st::ParamSource::Query => ParamSource::Query,
+                                st::ParamSource::Header => ParamSource::Header,
+                                st::ParamSource::State => ParamSource::State,
+                            },
+                        }
+                    });
+                    param
+                })
                 .collect(),
             is_async,
             self_opt: self_opt
                 .map(|selff| Box::new(self.named_format_to_function_parameter(selff))),
             return_type: Box::new(self.format_to_format(*ret)),
+            return_future_output: ret_future_output
+                .map(|output| Box::new(self.format_to_format(*output))),
+            call: match call {
+                st::CallKind::Unary => None,
+                st::CallKind::ServerStreaming { item } => Some(CallKind::ServerStreaming {
+                    item: self.format_to_format(item),
+                }),
+                st::CallKind::ClientStreaming { param, item } => Some(CallKind::ClientStreaming {
+                    param,
+                    item: self.format_to_format(item),
+                }),
+                st::CallKind::Bidirectional {
+                    param,
+                    request,
+                    response,
+                } => Some(CallKind::Bidirectional {
+                    param,
+                    request: self.format_to_format(request),
+                    response: self.format_to_format(response),
+                }),
+            },
+            return_ok: ret_ok.map(|ok| Box::new(self.format_to_format(*ok))),
+            return_err: ret_err.map(|err| Box::new(self.format_to_format(*err))),
+        }
+    }
+
+    fn named_function_to_function_declaration(
+        &self,
+        named: st::Named<st::FunctionFormat>,
+    ) -> FunctionDeclaration {
+        let (id_span, function_format, attrs) = self.unname(named);
+        let (id, id_location) = self.location_id(id_span);
+        FunctionDeclaration {
+            id,
+            id_location,
+            function: self.function_format_to_function_format(function_format),
+            attrs,
         }
     }
 
@@ -483,6 +1095,95 @@ impl TypeRootConverter {
             id_location,
         }
     }
+    fn route_to_operation(
+        &self,
+        function_id: &str,
+        route: st::Route,
+        function: &FunctionFormat,
+    ) -> OperationDeclaration {
+        let st::Route {
+            method,
+            path,
+            path_params,
+        } = route;
+        let (method, _) = self.location_id(method);
+        let (path, path_location) = self.location_id(path);
+        let in_path = path_params
+            .iter()
+            .map(|path_param| path_param.param)
+            .collect::<HashSet<_>>();
+        let path_params = path_params
+            .into_iter()
+            .map(|path_param| {
+                let param = &function.params[path_param.param];
+                let format = match (path_param.field, &param.format) {
+                    (Some(field), Format::Tuple(formats)) if field < formats.len() => {
+                        formats[field].clone()
+                    }
+                    _ => param.format.clone(),
+                };
+                OperationParameter {
+                    name: path_param.name,
+                    param: path_param.binding.unwrap_or_else(|| param.id.clone()),
+                    param_index: path_param.param,
+                    field: path_param.field,
+                    format,
+                }
+            })
+            .collect();
+
+        let has_extractors = function
+            .params
+            .iter()
+            .any(|param| param.extractor.is_some());
+        let in_query = matches!(
+            method.as_str(),
+            "GET" | "HEAD" | "DELETE" | "OPTIONS" | "TRACE"
+        );
+        let mut query_params = Vec::new();
+        let mut header_params = Vec::new();
+        let mut body_params = Vec::new();
+        for (idx, param) in function.params.iter().enumerate() {
+            if in_path.contains(&idx) {
+                continue;
+            }
+            let params = match param.extractor.as_ref().map(|extractor| &extractor.source) {
+                Some(ParamSource::Query) => &mut query_params,
+                Some(ParamSource::Header) => &mut header_params,
+                Some(ParamSource::Body) => &mut body_params,
+                Some(ParamSource::Path | ParamSource::State) => continue,
+                // e.g. a request of the framework, next to its extractors
+                None if has_extractors => continue,
+                None if in_query => &mut query_params,
+                None => &mut body_params,
+            };
+            params.push(OperationParameter {
+                name: param.id.clone(),
+                param: param.id.clone(),
+                param_index: idx,
+                field: None,
+                format: param.format.clone(),
+            });
+        }
+
+        OperationDeclaration {
+            function_id: function_id.to_string(),
+            method,
+            path,
+            path_location,
+            path_params,
+            query_params,
+            header_params,
+            body_params,
+            success: function
+                .return_ok
+                .as_deref()
+                .or(function.return_future_output.as_deref())
+                .unwrap_or(&function.return_type)
+                .clone(),
+            error: function.return_err.as_deref().cloned(),
+        }
+    }
     fn named_format_to_function_parameter(
         &self,
         named: st::Named<st::Format>,
@@ -494,6 +1195,7 @@ impl TypeRootConverter {
             format: self.format_to_format(format),
             id,
             id_location,
+            extractor: None,
         }
     }
     fn container_format_to_container_format(
@@ -507,12 +1209,12 @@ impl TypeRootConverter {
             st::ContainerFormat::NewTypeStruct(format) => {
                 ContainerFormat::NewTypeStruct(Box::new(self.format_to_format(*format)))
             }
-            st::ContainerFormat::TupleStruct(formats) => ContainerFormat::TupleStruct(
-                formats
+            st::ContainerFormat::TupleStruct(fields) => ContainerFormat::TupleStruct {
+                fields: fields
                     .into_iter()
-                    .map(|format| self.format_to_format(format))
+                    .map(|field| self.named_format_to_named_field(field))
                     .collect(),
-            ),
+            },
             st::ContainerFormat::Struct(fields) => ContainerFormat::Struct {
                 fields: {
                     fields
@@ -525,6 +1227,20 @@ impl TypeRootConverter {
                 repr: {
                     if attrs.serde_flags.contains_key("untagged") {
                         EnumRepresentation::Untagged
+                    } else if attrs.codegen_flags.contains_key("serde_repr")
+                        || attrs.rust_derives.iter().any(|(derive, _)| {
+                            derive == "Serialize_repr" || derive == "Deserialize_repr"
+                        })
+                    {
+                        EnumRepresentation::Integer {
+                            repr: attrs
+                                .rust_repr
+                                .iter()
+                                .map(|(repr, _)| repr.as_str())
+                                .find(|repr| st::INTEGER_REPRS.contains(repr))
+                                .unwrap_or("isize")
+                                .to_string(),
+                        }
                     } else {
                         match (
                             attrs.serde_attrs.get("tag").cloned(),
@@ -555,7 +1271,12 @@ impl TypeRootConverter {
                 variants: {
                     variants
                         .into_par_iter()
-                        .map(|(_index, named_variant_format)| {
+                        .map(|(index, mut named_variant_format)| {
+                            let discriminant = named_variant_format
+                                .rust_attrs
+                                .discriminant
+                                .take()
+                                .map(|discriminant| discriminant.value);
                             let (id_span, variant_format, attrs) =
                                 self.unname(named_variant_format);
                             let (id, id_location) = self.location_id(id_span);
@@ -564,31 +1285,24 @@ impl TypeRootConverter {
                                 st::VariantFormat::NewType(format) => {
                                     VariantFormat::NewType(Box::new(self.format_to_format(*format)))
                                 }
-                                st::VariantFormat::Tuple(formats) => VariantFormat::Tuple(
-                                    formats
+                                st::VariantFormat::Tuple(fields) => VariantFormat::Tuple {
+                                    fields: fields
                                         .into_iter()
-                                        .map(|format| self.format_to_format(format))
+                                        .map(|field| self.named_format_to_named_field(field))
                                         .collect(),
-                                ),
+                                },
                                 st::VariantFormat::Struct(fields) => VariantFormat::Struct {
                                     fields: fields
                                         .into_iter()
-                                        .map(|field| {
-                                            let (id_span, format, attrs) = self.unname(field);
-                                            let (id, id_location) = self.location_id(id_span);
-                                            NamedField {
-                                                attrs,
-                                                format: self.format_to_format(format),
-                                                id,
-                                                id_location,
-                                            }
-                                        })
+                                        .map(|field| self.named_format_to_named_field(field))
                                         .collect(),
                                 },
                             };
                             NamedVariant {
                                 id,
                                 id_location,
+                                index,
+                                discriminant,
                                 attrs,
                                 variant_format,
                             }
@@ -605,9 +1319,14 @@ enum GenCommand<'a> {
     Arg(&'a mut std::process::Command),
 }
 
-#[derive(Clone)]
+/// Which items to generate and how to resolve their attributes.
+///
+/// [Generation::default] selects the items without tags, or only the inclusions once there are any.
+#[derive(Clone, Default)]
 pub struct Generation {
-    tags: Vec<String>,
+    items: Selection,
+    targets: Vec<String>,
+    strict_dependencies: bool,
 }
 
 pub struct GenerationCmd<'a> {
@@ -618,14 +1337,84 @@ pub struct GenerationCmd<'a> {
 }
 
 impl Generation {
+    /// Items selected by a tag expression, like `api`, `api & !internal`, `api | admin`, `api-*`,
+    /// or `*` for every item, see [crate::TagExpr].
+    ///
+    /// Panics for a malformed expression, see [Generation::for_selection] for parsing it separately.
+    #[track_caller]
     pub fn for_tag(tag: &str) -> Self {
+        Generation::for_selection(TagSelection::parse(&[tag]).unwrap_or_else(|err| panic!("{err}")))
+    }
+
+    pub fn for_selection(items: impl Into<Selection>) -> Self {
         Generation {
-            tags: vec![tag.to_string()],
+            items: items.into(),
+            targets: Vec::new(),
+            strict_dependencies: false,
         }
     }
 
+    /// Also select items matching the tag expression, see [Generation::for_tag].
+    #[track_caller]
     pub fn include_tag(&mut self, tag: impl Into<String>) -> &mut Self {
-        self.tags.push(tag.into());
+        let tag = tag.into();
+        self.items
+            .tags
+            .push(&tag)
+            .unwrap_or_else(|err| panic!("{err}"));
+        self
+    }
+
+    /// Also select the items declared in a crate, e.g. `"billing"`
+    pub fn include_crate(&mut self, name: impl Into<String>) -> &mut Self {
+        self.items.include.push(ItemFilter::Crate(name.into()));
+        self
+    }
+
+    /// Leave out the items declared in a crate, even when they are otherwise selected
+    pub fn exclude_crate(&mut self, name: impl Into<String>) -> &mut Self {
+        self.items.exclude.push(ItemFilter::Crate(name.into()));
+        self
+    }
+
+    /// Also select the items declared in a module or its submodules, e.g. `"billing::invoices"`
+    pub fn include_module(&mut self, path: impl Into<String>) -> &mut Self {
+        self.items.include.push(ItemFilter::Module(path.into()));
+        self
+    }
+
+    /// Leave out the items declared in a module or its submodules, even when they are otherwise selected
+    pub fn exclude_module(&mut self, path: impl Into<String>) -> &mut Self {
+        self.items.exclude.push(ItemFilter::Module(path.into()));
+        self
+    }
+
+    /// Also select items by id, e.g. `"Invoice"`, or by path, e.g. `"billing::invoices::Invoice"`
+    pub fn include_ids(&mut self, ids: impl IntoIterator<Item = impl Into<String>>) -> &mut Self {
+        let ids = ids.into_iter().map(|id| ItemFilter::Id(id.into()));
+        self.items.include.extend(ids);
+        self
+    }
+
+    /// Leave out items by id or path, even when they are otherwise selected
+    pub fn exclude_ids(&mut self, ids: impl IntoIterator<Item = impl Into<String>>) -> &mut Self {
+        let ids = ids.into_iter().map(|id| ItemFilter::Id(id.into()));
+        self.items.exclude.extend(ids);
+        self
+    }
+
+    /// Fail with [MissingDependencies] when selected items reference types which aren't selected,
+    /// instead of including those types which are registered, as `included_as_dependency`.
+    pub fn strict_dependencies(&mut self) -> &mut Self {
+        self.strict_dependencies = true;
+        self
+    }
+
+    /// Resolve scoped attributes like `#[codegen(ts(as = "Date"))]` for the `"ts"` target as
+    /// `as = "Date"`, leaving out those of other targets.
+    /// Without targets, these are prefixed with their target instead, as `ts_as = "Date"`.
+    pub fn include_target(&mut self, target: impl Into<String>) -> &mut Self {
+        self.targets.push(target.into());
         self
     }
 
@@ -648,11 +1437,22 @@ impl Generation {
     }
 
     pub fn to_input_json_pretty(&self) -> String {
-        serde_json::to_string_pretty(&create_input_from_selection(self)).unwrap()
+        serde_json::to_string_pretty(&self.create_input()).unwrap()
     }
 
     pub fn to_input_json(&self) -> String {
-        serde_json::to_string(&create_input_from_selection(self)).unwrap()
+        serde_json::to_string(&self.create_input()).unwrap()
+    }
+
+    /// Like [Generation::to_input_json], with an error rather than a panic for [MissingDependencies].
+    pub fn try_to_input_json(&self) -> Result<String, MissingDependencies> {
+        Ok(serde_json::to_string(&create_input_from_selection(self)?).unwrap())
+    }
+
+    /// Panics for [MissingDependencies], with the list of them
+    #[track_caller]
+    fn create_input(&self) -> Input {
+        create_input_from_selection(self).unwrap_or_else(|err| panic!("{err}"))
     }
 }
 
@@ -737,7 +1537,10 @@ impl<'a> GenerationCmd<'a> {
 
     #[track_caller]
     fn generate(&mut self) -> Output {
-        let inputs = create_input_from_selection(self.selection);
+        let inputs = self.selection.create_input();
+        for warning in &inputs.warnings {
+            eprintln!("Input warning:\n{warning}")
+        }
         let stdout_output = match self.command {
             GenCommand::PipeInto(ref mut cmd) => {
                 let cmd_str = format!("{cmd:?}");
@@ -760,7 +1563,7 @@ impl<'a> GenerationCmd<'a> {
             GenCommand::Arg(ref mut cmd) => {
                 let cmd_str = format!("{cmd:?} <input-json>");
                 let child = cmd
-                    .arg(&serde_json::to_string(&inputs).unwrap())
+                    .arg(serde_json::to_string(&inputs).unwrap())
                     .stdout(std::process::Stdio::piped())
                     .spawn()
                     .map_err(|err| format!("Failure executing `{cmd_str}`: {err:?} "))
@@ -790,8 +1593,18 @@ impl<'a> GenerationCmd<'a> {
     }
 }
 
-fn create_input_from_selection(selection: &Generation) -> Input {
-    let tys = i_codegen_code::get_types_by_tags(&selection.tags);
+fn create_input_from_selection(selection: &Generation) -> Result<Input, MissingDependencies> {
+    let (tys, dependencies) = if selection.strict_dependencies {
+        let tys = i_codegen_code::get_types_by_selection(&selection.items);
+        (tys, HashSet::new())
+    } else {
+        let (mut tys, unselected) =
+            i_codegen_code::get_types_and_unselected_by_selection(&selection.items);
+        let dependencies = include_dependencies(selection, &mut tys, unselected);
+        (tys, dependencies)
+    };
+    let ids: Arc<HashSet<String>> = Arc::new(root_ids(&tys));
+    let selection = Arc::new(selection.clone());
     let current_directory = std::env::current_dir()
         .expect("getting current directory in order to find source files for line number mapping");
     let type_root_converters: HashMap<String, TypeRootConverter> = tys
@@ -806,7 +1619,7 @@ fn create_input_from_selection(selection: &Generation) -> Input {
                     Err(_) => {
                         // try going up on directory... hacky...
                         match std::fs::File::open(
-                            &current_directory.parent().unwrap().join(&file_name),
+                            current_directory.parent().unwrap().join(&file_name),
                         ) {
                             Ok(file) => file,
                             Err(_err) => {
@@ -820,6 +1633,10 @@ fn create_input_from_selection(selection: &Generation) -> Input {
                                             newlines: vec![0usize],
                                             is_crlf: false,
                                         },
+                                        doc_includes: Vec::new(),
+                                        captured_defaults: Vec::new(),
+                                        ids: ids.clone(),
+                                        selection: selection.clone(),
                                     },
                                 );
                             }
@@ -834,27 +1651,68 @@ fn create_input_from_selection(selection: &Generation) -> Input {
                     file_name,
                     line_number_override: None,
                     lines: SourceLineNumberIndex::new(file),
+                    doc_includes: Vec::new(),
+                    captured_defaults: Vec::new(),
+                    ids: ids.clone(),
+                    selection: selection.clone(),
                 },
             )
         })
         .collect();
 
+    if selection.strict_dependencies {
+        let type_ids = type_ids(&tys);
+        let mut missing = Vec::new();
+        for root in &tys {
+            let mut converter = type_root_converters.get(&root.file).unwrap().clone();
+            converter.line_number_override = Some(root.line);
+            for (ident, at) in type_references(root) {
+                if !type_ids.contains(ident) {
+                    let (_, LocationID(location)) = converter.location_id(at.clone());
+                    missing.push(MissingReference {
+                        ident: ident.to_string(),
+                        referenced_by: root.inner.rust_ident.value.clone(),
+                        location,
+                    });
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(MissingDependencies(missing));
+        }
+    }
+
     let mut functions = Vec::new();
+    let mut constants = Vec::new();
+    let mut aliases = Vec::new();
+    let mut services = Vec::new();
+    let mut interfaces = Vec::new();
+    let mut operations = Vec::new();
     let mut declarations = Vec::<InputDeclaration>::new();
+    let mut warnings = Vec::new();
     for TypeRoot {
         extras,
         file,
         line,
+        module_path: _,
         inner,
+        doc_includes,
+        captured_defaults,
+        value,
+        warnings: root_warnings,
     } in tys
     {
+        warnings.extend(root_warnings);
         let mut converter = type_root_converters.get(&file).unwrap().clone();
         converter.line_number_override = Some(line);
+        converter.doc_includes = doc_includes;
+        converter.captured_defaults = captured_defaults;
         let (root_id_span, root_item, attrs) = converter.unname(inner);
         let (id, id_location) = converter.location_id(root_id_span);
         match root_item {
             st::RootItem::Container(container_format) => {
                 declarations.push(InputDeclaration {
+                    included_as_dependency: dependencies.contains(&id),
                     id,
                     id_location,
                     container_kind: converter
@@ -862,12 +1720,64 @@ fn create_input_from_selection(selection: &Generation) -> Input {
                     attrs,
                 });
             }
-            st::RootItem::Function(function_format) => {
+            st::RootItem::Function(mut function_format) => {
+                let route = function_format.route.take();
+                let function = converter.function_format_to_function_format(function_format);
+                if let Some(route) = route {
+                    operations.push(converter.route_to_operation(&id, route, &function));
+                }
                 functions.push(FunctionDeclaration {
                     id,
                     id_location,
-                    function: converter.function_format_to_function_format(function_format),
+                    function,
+                    attrs,
+                });
+            }
+            st::RootItem::Constant(st::ConstantFormat { is_static, format }) => {
+                constants.push(ConstantDeclaration {
+                    id,
+                    id_location,
+                    attrs,
+                    is_static,
+                    format: converter.format_to_format(format),
+                    value,
+                });
+            }
+            st::RootItem::Alias(target) => {
+                aliases.push(AliasDeclaration {
+                    included_as_dependency: dependencies.contains(&id),
+                    id,
+                    id_location,
                     attrs,
+                    target: converter.format_to_format(target),
+                });
+            }
+            st::RootItem::Service(st::ServiceFormat {
+                self_type,
+                trait_type,
+                methods,
+            }) => {
+                services.push(ServiceDeclaration {
+                    id,
+                    id_location,
+                    attrs,
+                    self_type: converter.format_to_format(self_type),
+                    trait_type: trait_type.map(|trait_type| converter.format_to_format(trait_type)),
+                    methods: methods
+                        .into_iter()
+                        .map(|method| converter.named_function_to_function_declaration(method))
+                        .collect(),
+                });
+            }
+            st::RootItem::Interface(st::InterfaceFormat { methods }) => {
+                interfaces.push(InterfaceDeclaration {
+                    id,
+                    id_location,
+                    attrs,
+                    methods: methods
+                        .into_iter()
+                        .map(|method| converter.named_function_to_function_declaration(method))
+                        .collect(),
                 });
             }
         }
@@ -876,6 +1786,7 @@ fn create_input_from_selection(selection: &Generation) -> Input {
             let (id_span, container_format, attrs) = converter.unname(extra);
             let (id, id_location) = converter.location_id(id_span);
             declarations.push(InputDeclaration {
+                included_as_dependency: false,
                 id,
                 id_location,
                 container_kind: converter
@@ -885,8 +1796,212 @@ fn create_input_from_selection(selection: &Generation) -> Input {
         }
     }
 
-    Input {
+    Ok(Input {
         declarations,
         functions,
+        constants,
+        aliases,
+        services,
+        interfaces,
+        operations,
+        warnings,
+    })
+}
+
+/// References to types which aren't selected by a [Generation::strict_dependencies] generation.
+#[derive(Debug)]
+pub struct MissingDependencies(pub Vec<MissingReference>);
+
+/// e.g. `Money` referenced by the `total` field of `Order`
+#[derive(Debug)]
+pub struct MissingReference {
+    /// e.g. `"Money"`
+    pub ident: String,
+    /// e.g. `"Order"`
+    pub referenced_by: String,
+    /// The location of the field, parameter or item with the reference, e.g. `"L(src/order.rs:12 #B240-B245)"`
+    pub location: String,
+}
+
+impl std::fmt::Display for MissingDependencies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "types referenced by the generation aren't selected:")?;
+        for MissingReference {
+            ident,
+            referenced_by,
+            location,
+        } in &self.0
+        {
+            write!(
+                f,
+                "\n * `{ident}` referenced by `{referenced_by}` at {location}"
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MissingDependencies {}
+
+/// Ids of the items and their extra declarations
+fn root_ids(tys: &[TypeRoot]) -> HashSet<String> {
+    tys.iter()
+        .flat_map(|root| {
+            std::iter::once(&root.inner.rust_ident.value)
+                .chain(root.extras.iter().map(|extra| &extra.rust_ident.value))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Ids of the declarations and aliases, and of the extra declarations of every item,
+/// which are what a [st::Format::TypeName] refers to, unlike a function or service of the same name
+fn type_ids(tys: &[TypeRoot]) -> HashSet<String> {
+    tys.iter()
+        .flat_map(|root| {
+            let is_type = matches!(
+                root.inner.value,
+                st::RootItem::Container(_) | st::RootItem::Alias(_)
+            );
+            is_type
+                .then_some(&root.inner.rust_ident.value)
+                .into_iter()
+                .chain(root.extras.iter().map(|extra| &extra.rust_ident.value))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Adds the declarations and aliases among the `unselected` items which are referenced by the
+/// selected items, and by those in turn, unless they are excluded by the generation.
+/// Returns the ids of the added items.
+fn include_dependencies(
+    generation: &Generation,
+    tys: &mut Vec<TypeRoot>,
+    mut unselected: Vec<TypeRoot>,
+) -> HashSet<String> {
+    unselected.retain(|root| {
+        matches!(
+            root.inner.value,
+            st::RootItem::Container(_) | st::RootItem::Alias(_)
+        ) && !generation
+            .items
+            .exclude
+            .iter()
+            .any(|filter| filter.matches(&root.module_path, &root.inner.rust_ident.value))
+    });
+    let mut dependencies = HashSet::new();
+    loop {
+        let ids = type_ids(tys);
+        let missing = tys
+            .iter()
+            .flat_map(type_references)
+            .map(|(ident, _)| ident)
+            .filter(|ident| !ids.contains(*ident))
+            .map(String::from)
+            .collect::<HashSet<_>>();
+        let (found, rest): (Vec<_>, Vec<_>) = unselected
+            .into_iter()
+            .partition(|root| missing.contains(&root.inner.rust_ident.value));
+        unselected = rest;
+        if found.is_empty() {
+            break;
+        }
+        dependencies.extend(found.iter().map(|root| root.inner.rust_ident.value.clone()));
+        tys.extend(found);
+    }
+    dependencies
+}
+
+/// The [st::Format::TypeName]s of an item, with the field, parameter or item which references each.
+/// Type parameters of containers and aliases are left out.
+fn type_references(root: &TypeRoot) -> Vec<(&str, &st::Spanned<String>)> {
+    struct References<'a> {
+        generics: Vec<&'a str>,
+        found: Vec<(&'a str, &'a st::Spanned<String>)>,
+    }
+    impl<'a> References<'a> {
+        fn format(&mut self, format: &'a st::Format, at: &'a st::Spanned<String>) {
+            match format {
+                st::Format::TypeName { ident, generics } => {
+                    if !self.generics.contains(&ident.as_str()) {
+                        self.found.push((ident, at));
+                    }
+                    for generic in generics {
+                        self.format(generic, at);
+                    }
+                }
+                st::Format::Option(inner) | st::Format::Seq(inner) => self.format(inner, at),
+                st::Format::Map { key, value } => {
+                    self.format(key, at);
+                    self.format(value, at);
+                }
+                st::Format::Tuple(formats) => {
+                    for format in formats {
+                        self.format(format, at);
+                    }
+                }
+                st::Format::TupleArray { content, .. } => self.format(content, at),
+                _ => {}
+            }
+        }
+        fn fields(&mut self, fields: &'a [st::Named<st::Format>]) {
+            for field in fields {
+                self.format(&field.value, &field.rust_ident);
+            }
+        }
+        fn container(&mut self, container: &'a st::ContainerFormat, at: &'a st::Spanned<String>) {
+            match container {
+                st::ContainerFormat::UnitStruct => {}
+                st::ContainerFormat::NewTypeStruct(format) => self.format(format, at),
+                st::ContainerFormat::TupleStruct(fields) | st::ContainerFormat::Struct(fields) => {
+                    self.fields(fields)
+                }
+                st::ContainerFormat::Enum(variants) => {
+                    for variant in variants.values() {
+                        match &variant.value {
+                            st::VariantFormat::Unit => {}
+                            st::VariantFormat::NewType(format) => {
+                                self.format(format, &variant.rust_ident)
+                            }
+                            st::VariantFormat::Tuple(fields)
+                            | st::VariantFormat::Struct(fields) => self.fields(fields),
+                        }
+                    }
+                }
+            }
+        }
+        fn function(&mut self, function: &'a st::FunctionFormat, at: &'a st::Spanned<String>) {
+            self.fields(&function.params);
+            self.format(&function.ret, at);
+        }
+    }
+
+    let mut references = References {
+        generics: root
+            .inner
+            .rust_generics
+            .iter()
+            .map(|generic| generic.value.as_str())
+            .collect(),
+        found: Vec::new(),
+    };
+    let at = &root.inner.rust_ident;
+    match &root.inner.value {
+        st::RootItem::Container(container) => references.container(container, at),
+        st::RootItem::Function(function) => references.function(function, at),
+        st::RootItem::Constant(constant) => references.format(&constant.format, at),
+        st::RootItem::Alias(target) => references.format(target, at),
+        // the implementing type and trait aren't data, so only their methods are
+        st::RootItem::Service(st::ServiceFormat { methods, .. })
+        | st::RootItem::Interface(st::InterfaceFormat { methods }) => {
+            for method in methods {
+                references.function(&method.value, &method.rust_ident);
+            }
+        }
+    }
+    for extra in &root.extras {
+        references.container(&extra.value, &extra.rust_ident);
     }
+    references.found
 }
@@ -0,0 +1,310 @@
+use i_codegen_derive::CodegenInternal;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+/// Doc comments split up into their markdown parts.
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+pub(super) struct Docs {
+    /// The first paragraph, e.g. `"Test doc comment\nSecond line"`.
+    /// `None` if the docs begin with a code block or heading.
+    summary: Option<String>,
+    /// Everything after the summary and before the first heading.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    body: Option<String>,
+    /// Headed sections like `# Examples` or `# Errors`, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    sections: Vec<DocSection>,
+    /// Fenced code blocks from anywhere in the docs, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    code_blocks: Vec<DocCodeBlock>,
+    /// Links like `[BasicEnum]`, `[the enum](BasicEnum)` or `[serde](https://serde.rs)`, in order.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    links: Vec<DocLink>,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct DocSection {
+    /// e.g. `"Examples"` for `# Examples`
+    heading: String,
+    /// e.g. `1` for `# Examples` and `2` for `## Examples`
+    level: usize,
+    /// The markdown between this heading and the next one.
+    content: String,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct DocCodeBlock {
+    /// e.g. `"sh"` for ` ```sh ` or `"rust"` for ` ```rust,ignore `.
+    /// `None` when unlabeled, which rustdoc treats as Rust.
+    lang: Option<String>,
+    code: String,
+}
+
+#[derive(Serialize, Debug, CodegenInternal)]
+#[codegen(tags = "derive-codegen-internal")]
+struct DocLink {
+    /// e.g. `"`BasicEnum`"` for ``[`BasicEnum`]``
+    text: String,
+    /// e.g. `"BasicEnum::A"` for `[the A variant](BasicEnum::A)`, or a URL
+    target: String,
+    /// The id of the declaration or function linked to, when it is part of this generation.
+    /// Links to members like `BasicEnum::A` resolve to the id of `BasicEnum`.
+    id: Option<String>,
+    /// e.g. `"A"` for `BasicEnum::A`, when the link was resolved through its parent.
+    member: Option<String>,
+}
+
+pub(super) fn parse_docs(docs: &str, ids: &HashSet<String>) -> Docs {
+    let mut preamble = Vec::<&str>::new();
+    let mut sections = Vec::<(String, usize, Vec<&str>)>::new();
+    let mut code_blocks = Vec::new();
+    // lines outside of code blocks, for finding links
+    let mut prose = Vec::<&str>::new();
+    // (fence, lang, code lines)
+    let mut fenced: Option<(&str, Option<String>, Vec<&str>)> = None;
+
+    for line in docs.lines() {
+        let trimmed = line.trim_start();
+        if let Some((fence, lang, code)) = fenced.as_mut() {
+            if trimmed.starts_with(*fence) && trimmed[fence.len()..].trim().is_empty() {
+                code_blocks.push(DocCodeBlock {
+                    lang: lang.take(),
+                    code: code.join("\n"),
+                });
+                fenced = None;
+            } else {
+                code.push(line);
+            }
+        } else if let Some(fence) = code_fence(trimmed) {
+            let info = trimmed[fence.len()..].trim();
+            let lang = info
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .next()
+                .filter(|lang| !lang.is_empty())
+                .map(String::from);
+            fenced = Some((fence, lang, Vec::new()));
+        } else if let Some((level, heading)) = heading(line) {
+            sections.push((heading.to_string(), level, Vec::new()));
+            continue;
+        } else {
+            prose.push(line);
+        }
+
+        match sections.last_mut() {
+            Some((_, _, content)) => content.push(line),
+            None => preamble.push(line),
+        }
+    }
+    // unclosed code blocks run to the end of the docs
+    if let Some((_, lang, code)) = fenced {
+        code_blocks.push(DocCodeBlock {
+            lang,
+            code: code.join("\n"),
+        });
+    }
+
+    // the summary is the first paragraph, which ends at a blank line or code block
+    let summary_len = preamble
+        .iter()
+        .position(|line| line.trim().is_empty() || code_fence(line.trim_start()).is_some())
+        .unwrap_or(preamble.len());
+    let (summary, body) = preamble.split_at(summary_len);
+
+    Docs {
+        summary: join_trimmed(summary),
+        body: join_trimmed(body),
+        sections: sections
+            .into_iter()
+            .map(|(heading, level, content)| DocSection {
+                heading,
+                level,
+                content: join_trimmed(&content).unwrap_or_default(),
+            })
+            .collect(),
+        code_blocks,
+        links: find_links(&prose, ids),
+    }
+}
+
+/// e.g. `Some("```")` for ` ```rust `
+fn code_fence(trimmed_line: &str) -> Option<&str> {
+    ["```", "~~~"].into_iter().find_map(|marker| {
+        if trimmed_line.starts_with(marker) {
+            let len = trimmed_line
+                .chars()
+                .take_while(|c| marker.starts_with(*c))
+                .count();
+            Some(&trimmed_line[..len])
+        } else {
+            None
+        }
+    })
+}
+
+/// e.g. `Some((1, "Examples"))` for `# Examples`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+        Some((level, line[level..].trim()))
+    } else {
+        None
+    }
+}
+
+fn join_trimmed(lines: &[&str]) -> Option<String> {
+    let joined = lines.join("\n");
+    let trimmed = joined.trim_matches('\n').trim_end();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+fn find_links(prose: &[&str], ids: &HashSet<String>) -> Vec<DocLink> {
+    // reference definitions like `[BasicEnum]: crate::BasicEnum`
+    let mut definitions = BTreeMap::<&str, &str>::new();
+    let mut lines = Vec::new();
+    for line in prose {
+        match reference_definition(line) {
+            Some((label, target)) => {
+                definitions.insert(label, target);
+            }
+            None => lines.push(*line),
+        }
+    }
+
+    let mut links = Vec::new();
+    for line in lines {
+        let mut rest = line;
+        while let Some(start) = rest.find(['[', '`']) {
+            let (before, from) = rest.split_at(start);
+            if from.starts_with('`') {
+                // skip over inline code
+                let ticks = from.chars().take_while(|c| *c == '`').count();
+                rest = match from[ticks..].find(&from[..ticks]) {
+                    Some(end) => &from[ticks + end + ticks..],
+                    None => &from[ticks..],
+                };
+                continue;
+            }
+            let text_len = match closing_bracket(from) {
+                Some(text_len) => text_len,
+                None => break,
+            };
+            let text = &from[1..text_len];
+            let after = &from[text_len + 1..];
+            let is_image = before.ends_with('!');
+            let (target, after) = if let Some(inline) = after.strip_prefix('(') {
+                match inline.find(')') {
+                    Some(end) => (Some(inline[..end].trim()), &inline[end + 1..]),
+                    None => (None, after),
+                }
+            } else if let Some(reference) = after.strip_prefix('[') {
+                match reference.find(']') {
+                    Some(end) => {
+                        let label = if end == 0 { text } else { &reference[..end] };
+                        (
+                            Some(definitions.get(label).copied().unwrap_or(label)),
+                            &reference[end + 1..],
+                        )
+                    }
+                    None => (None, after),
+                }
+            } else {
+                // only shortcut links which could be intra-doc links
+                let target = definitions.get(text).copied().unwrap_or(text);
+                (
+                    Some(target).filter(|target| intra_doc_path(target).is_some()),
+                    after,
+                )
+            };
+            if let (Some(target), false) = (target, is_image) {
+                let (id, member) = resolve(target, ids);
+                links.push(DocLink {
+                    text: text.to_string(),
+                    target: target.to_string(),
+                    id,
+                    member,
+                });
+                rest = after;
+            } else {
+                rest = &from[1..];
+            }
+        }
+    }
+    links
+}
+
+/// e.g. `Some(("BasicEnum", "crate::BasicEnum"))` for `[BasicEnum]: crate::BasicEnum`
+fn reference_definition(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_start().strip_prefix('[')?;
+    let end = line.find("]:")?;
+    Some((&line[..end], line[end + 2..].trim()))
+}
+
+/// Byte index of the `]` matching the `[` at the start of `from`
+fn closing_bracket(from: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, c) in from.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// e.g. `Some(["BasicEnum", "A"])` for ``struct@`BasicEnum::A` `` or `None` for a URL
+fn intra_doc_path(target: &str) -> Option<Vec<&str>> {
+    let target = target.trim_matches('`');
+    let target = match target.split_once('@') {
+        Some((disambiguator, path)) if disambiguator.chars().all(char::is_alphabetic) => path,
+        _ => target,
+    };
+    let target = target
+        .strip_suffix("()")
+        .or_else(|| target.strip_suffix('!'))
+        .unwrap_or(target);
+    let segments = target
+        .strip_prefix("::")
+        .unwrap_or(target)
+        .split("::")
+        .collect::<Vec<_>>();
+    let is_ident = |segment: &&str| {
+        segment
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    };
+    if segments.iter().all(is_ident) {
+        Some(segments)
+    } else {
+        None
+    }
+}
+
+/// Finds the declaration or function id for an intra-doc link, and the member linked to, if any.
+fn resolve(target: &str, ids: &HashSet<String>) -> (Option<String>, Option<String>) {
+    let segments = match intra_doc_path(target) {
+        Some(segments) => segments,
+        None => return (None, None),
+    };
+    match segments.as_slice() {
+        [.., last] if ids.contains(*last) => (Some(last.to_string()), None),
+        [.., parent, member] if ids.contains(*parent) => {
+            (Some(parent.to_string()), Some(member.to_string()))
+        }
+        _ => (None, None),
+    }
+}
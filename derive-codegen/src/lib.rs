@@ -1,13 +1,19 @@
-pub use generate::{Generation, GenerationCmd};
+pub use generate::{Generation, GenerationCmd, MissingDependencies, MissingReference};
 pub extern crate i_codegen_derive;
 // re-export macros (note pub)
+pub use i_codegen_code::dispatch::{DispatchError, Dispatcher};
+pub use i_codegen_code::selection::{ItemFilter, Selection};
+pub use i_codegen_code::tags::{TagExpr, TagExprError, TagSelection};
 #[doc(hidden)]
-pub use i_codegen_code::{linkme, Context, CODEGEN_ITEMS};
+pub use i_codegen_code::{dispatch, linkme, serde_json, Context, RuntimeValues, CODEGEN_ITEMS};
 /// Test documentation
 pub use i_codegen_derive::Codegen;
-pub use i_codegen_derive::fn_codegen;
+pub use i_codegen_derive::{const_codegen, fn_codegen};
 
 #[cfg(test)]
 mod test;
+/// Allows tests to use the macros which refer to `::derive_codegen`
+#[cfg(test)]
+extern crate self as derive_codegen;
 
 mod generate;
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::generate::Generation;
 
 mod random_serde;
+mod serde_attrs;
 
 #[derive(Codegen, Deserialize)]
 #[codegen(tags = "fsharp")]
@@ -42,7 +43,25 @@ enum BasicEnum {
 /// These renames don't actually affect the generation.
 /// See [Feature request: Allow `#[serde(rename = "...")]` on tuple struct / tuple variant member fields #1510](https://github.com/serde-rs/serde/issues/1510)
 #[derive(Codegen, Serialize, Deserialize, Debug, Eq, Clone, PartialEq)]
-struct ATuplePartiallyNamed(#[serde(rename = "x")] usize, #[serde(rename = "y")] usize);
+#[codegen(tags = "tuple-members")]
+struct ATuplePartiallyNamed(
+    /// Horizontal position
+    #[serde(rename = "x")]
+    usize,
+    #[serde(rename = "y")]
+    #[codegen(ts_as = "number")]
+    usize,
+);
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "tuple-members")]
+enum ATupleVariant {
+    Point(
+        /// Horizontal position
+        usize,
+        usize,
+    ),
+}
 
 #[cfg(test)]
 mod test_sers {
@@ -87,81 +106,351 @@ struct ActionResult {
     result: Result<BasicallyOther, String>,
 }
 
+#[derive(Codegen)]
+#[codegen(tags = "rust-attrs")]
+#[deprecated(since = "0.1.0", note = "Use `Basic` instead")]
+#[non_exhaustive]
+struct Legacy {
+    #[doc(hidden)]
+    internal: usize,
+    #[deprecated = "No longer set"]
+    old: String,
+}
+
+#[derive(Codegen)]
+#[codegen(tags = "rust-attrs")]
+#[must_use]
+#[non_exhaustive]
+enum Status {
+    Active,
+    #[deprecated]
+    Paused,
+    #[non_exhaustive]
+    Failed {
+        reason: String,
+    },
+}
+
+#[i_codegen_derive::fn_codegen]
+#[i_codegen_derive::codegen(tags = "rust-attrs")]
+#[deprecated(since = "0.2.0")]
+#[must_use = "the status should be checked"]
+fn check_status(basic: Basic) -> Status {
+    Status::Active
+}
+
+#[derive(Codegen)]
+#[codegen(tags = "visibility")]
+pub struct Account {
+    pub id: usize,
+    pub(crate) email: String,
+    #[cfg(test)]
+    pub(in crate::test) note: Option<String>,
+    secret: String,
+}
+
+#[i_codegen_derive::fn_codegen]
+#[i_codegen_derive::codegen(tags = "visibility")]
+pub(crate) fn find_account(#[cfg_attr(test, allow(unused_variables))] id: usize) -> Account {
+    Account {
+        id: 0,
+        email: String::new(),
+        note: None,
+        secret: String::new(),
+    }
+}
+
 #[test]
-fn test_generate() {
-    insta::assert_snapshot!(Generation::for_tag("docs").to_input_json_pretty(), @r###"
+fn test_visibility() {
+    insta::assert_snapshot!(Generation::for_tag("visibility").to_input_json_pretty(), @r###"
     {
       "declarations": [
         {
-          "id": "Basically",
-          "id_location": "L(derive-codegen/src/test.rs:65 #B1839-B1848)",
-          "rust_docs": "Test doc comment\nSecond line\n```sh\nSome code\n```\nReference to [BasicEnum].",
-          "serde_attrs": {
-            "rename": [
-              "basically",
-              "L(derive-codegen/src/test.rs:65 #B1792-B1803)"
+          "id": "Account",
+          "id_location": "L(derive-codegen/src/test.rs:142 #B3720-B3727)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "visibility",
+              "L(derive-codegen/src/test.rs:142 #B3694-B3706)"
             ]
           },
-          "codegen_attrs": {
+          "codegen_attrs_repeated": {
             "tags": [
-              "docs",
-              "L(derive-codegen/src/test.rs:65 #B1823-B1829)"
+              [
+                "visibility",
+                "L(derive-codegen/src/test.rs:142 #B3694-B3706)"
+              ]
             ]
           },
+          "rust_visibility": [
+            "pub",
+            "L(derive-codegen/src/test.rs:142 #B3709-B3712)"
+          ],
           "container_kind": {
             "Struct": {
               "fields": [
                 {
-                  "id": "a",
-                  "id_location": "L(derive-codegen/src/test.rs:65 #B1875-B1876)",
-                  "rust_docs": "Doc comment",
+                  "id": "id",
+                  "id_location": "L(derive-codegen/src/test.rs:142 #B3738-B3740)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "pub",
+                    "L(derive-codegen/src/test.rs:142 #B3734-B3737)"
+                  ],
                   "format": "USIZE"
                 },
                 {
-                  "id": "b",
-                  "id_location": "L(derive-codegen/src/test.rs:65 #B1889-B1890)",
+                  "id": "email",
+                  "id_location": "L(derive-codegen/src/test.rs:142 #B3764-B3769)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "pub(crate)",
+                    "L(derive-codegen/src/test.rs:142 #B3753-B3756)"
+                  ],
+                  "format": "Str"
+                },
+                {
+                  "id": "note",
+                  "id_location": "L(derive-codegen/src/test.rs:142 #B3820-B3824)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "pub(in crate::test)",
+                    "L(derive-codegen/src/test.rs:142 #B3800-B3803)"
+                  ],
+                  "rust_cfg": [
+                    [
+                      "test",
+                      "L(derive-codegen/src/test.rs:142 #B3789-B3793)"
+                    ]
+                  ],
+                  "format": {
+                    "Option": "Str"
+                  }
+                },
+                {
+                  "id": "secret",
+                  "id_location": "L(derive-codegen/src/test.rs:142 #B3846-B3852)",
                   "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:142 #B3846-B3852)"
+                  ],
                   "format": "Str"
                 }
               ]
             }
           }
-        },
+        }
+      ],
+      "functions": [
         {
-          "id": "BasicallyOther",
-          "id_location": "L(derive-codegen/src/test.rs:74 #B1968-B1982)",
+          "id": "find_account",
+          "id_location": "L(derive-codegen/src/test.rs:152 #B3961-B3973)",
           "rust_docs": null,
           "codegen_attrs": {
             "tags": [
-              "docs",
-              "L(derive-codegen/src/test.rs:74 #B1952-B1958)"
+              "visibility",
+              "L(derive-codegen/src/test.rs:152 #B3932-B3944)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "visibility",
+                "L(derive-codegen/src/test.rs:152 #B3932-B3944)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "pub(crate)",
+            "L(derive-codegen/src/test.rs:152 #B3947-B3950)"
+          ],
+          "function": {
+            "is_async": false,
+            "self_opt": null,
+            "receiver": null,
+            "params": [
+              {
+                "id": "id",
+                "id_location": "L(derive-codegen/src/test.rs:152 #B4017-B4019)",
+                "rust_docs": null,
+                "rust_cfg_attr": [
+                  {
+                    "predicate": "test",
+                    "attrs": [
+                      "allow(unused_variables)"
+                    ],
+                    "location": "L(derive-codegen/src/test.rs:152 #B3985-B3989)"
+                  }
+                ],
+                "format": "USIZE"
+              }
+            ],
+            "return_type": {
+              "TypeName": {
+                "ident": "Account",
+                "generics": []
+              }
+            }
+          }
+        }
+      ],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "rust-cfg")]
+enum Plan {
+    Free,
+    #[cfg(test)]
+    Trial,
+    /// Removed by the compiler before `Codegen` sees it
+    #[cfg(not(test))]
+    Enterprise,
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "rust-cfg")]
+struct Subscription {
+    /// Only seen as the `#[serde(rename)]` it expands to
+    #[cfg_attr(test, serde(rename = "planName"))]
+    plan: Plan,
+}
+
+/// Declared with `#[cfg(test)]`, which the compiler removes before `Codegen` sees it
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "rust-cfg", cfg = "test")]
+#[cfg(test)]
+struct TrialReport {
+    days: u32,
+}
+
+/// `#[cfg_attr]`s of parameters are seen as written, unlike those of fields
+#[i_codegen_derive::fn_codegen(tags = "rust-cfg", cfg = "feature = \"admin\"")]
+fn start_subscription(#[cfg_attr(test, allow(unused_variables))] plan: Plan) -> Subscription {
+    Subscription { plan }
+}
+
+#[i_codegen_derive::codegen(tags = "rust-cfg", cfg = "test")]
+#[cfg(test)]
+type PlanName = String;
+
+#[test]
+fn test_rust_cfg() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("rust-cfg").to_input_json()).unwrap();
+    let mut lines = Vec::new();
+    let mut push = |id: String, item: &serde_json::Value| {
+        let cfg = item["rust_cfg"].as_array().into_iter().flatten();
+        let cfg_attr = item["rust_cfg_attr"].as_array().into_iter().flatten();
+        let serde_attrs = item["serde_attrs"].as_object().into_iter().flatten();
+        lines.push(format!(
+            "{id}: cfg {} cfg_attr {} serde_attrs {}",
+            serde_json::Value::from_iter(cfg.map(|cfg| cfg[0].clone())),
+            serde_json::Value::from_iter(cfg_attr.map(|cfg_attr| cfg_attr["predicate"].clone())),
+            serde_json::Value::from_iter(serde_attrs.map(|(key, _)| key.clone())),
+        ));
+    };
+    for section in ["declarations", "functions", "aliases"] {
+        for item in input[section].as_array().unwrap() {
+            let id = item["id"].as_str().unwrap();
+            push(id.to_string(), item);
+            let kind = &item["container_kind"];
+            for variant in kind["Enum"]["variants"].as_array().into_iter().flatten() {
+                push(
+                    format!("{id}::{}", variant["id"].as_str().unwrap()),
+                    variant,
+                );
+            }
+            for field in kind["Struct"]["fields"].as_array().into_iter().flatten() {
+                push(format!("{id}.{}", field["id"].as_str().unwrap()), field);
+            }
+            for param in item["function"]["params"].as_array().into_iter().flatten() {
+                push(format!("{id}({})", param["id"].as_str().unwrap()), param);
+            }
+        }
+    }
+    insta::assert_snapshot!(lines.join("\n"), @r###"
+    Plan: cfg [] cfg_attr [] serde_attrs []
+    Plan::Free: cfg [] cfg_attr [] serde_attrs []
+    Plan::Trial: cfg ["test"] cfg_attr [] serde_attrs []
+    TrialReport: cfg ["test"] cfg_attr [] serde_attrs []
+    TrialReport.days: cfg [] cfg_attr [] serde_attrs []
+    Subscription: cfg [] cfg_attr [] serde_attrs []
+    Subscription.plan: cfg [] cfg_attr [] serde_attrs ["rename"]
+    start_subscription: cfg ["feature = \"admin\""] cfg_attr [] serde_attrs []
+    start_subscription(plan): cfg [] cfg_attr ["test"] serde_attrs []
+    PlanName: cfg ["test"] cfg_attr [] serde_attrs []
+    "###);
+}
+
+#[test]
+fn test_rust_attrs() {
+    insta::assert_snapshot!(Generation::for_tag("rust-attrs").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "Legacy",
+          "id_location": "L(derive-codegen/src/test.rs:109 #B3125-B3131)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "rust-attrs",
+              "L(derive-codegen/src/test.rs:109 #B3024-B3036)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "rust-attrs",
+                "L(derive-codegen/src/test.rs:109 #B3024-B3036)"
+              ]
             ]
           },
+          "rust_deprecated": {
+            "since": "0.1.0",
+            "note": "Use `Basic` instead",
+            "location": "L(derive-codegen/src/test.rs:109 #B3041-B3051)"
+          },
+          "rust_non_exhaustive": "L(derive-codegen/src/test.rs:109 #B3102-B3116)",
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:109 #B3125-B3131)"
+          ],
           "container_kind": {
             "Struct": {
               "fields": [
                 {
-                  "id": "usize_opt",
-                  "id_location": "L(derive-codegen/src/test.rs:74 #B2074-B2083)",
-                  "rust_docs": "Other option",
-                  "serde_attrs": {
-                    "alias": [
-                      "usize",
-                      "L(derive-codegen/src/test.rs:74 #B2060-B2067)"
-                    ],
-                    "rename": [
-                      "usizeOpt",
-                      "L(derive-codegen/src/test.rs:74 #B2027-B2037)"
-                    ]
-                  },
-                  "format": {
-                    "Option": "USIZE"
-                  }
+                  "id": "internal",
+                  "id_location": "L(derive-codegen/src/test.rs:109 #B3157-B3165)",
+                  "rust_docs": null,
+                  "rust_doc_hidden": "L(derive-codegen/src/test.rs:109 #B3144-B3150)",
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:109 #B3157-B3165)"
+                  ],
+                  "format": "USIZE"
                 },
                 {
-                  "id": "b",
-                  "id_location": "L(derive-codegen/src/test.rs:74 #B2104-B2105)",
+                  "id": "old",
+                  "id_location": "L(derive-codegen/src/test.rs:109 #B3214-B3217)",
                   "rust_docs": null,
+                  "rust_deprecated": {
+                    "since": null,
+                    "note": "No longer set",
+                    "location": "L(derive-codegen/src/test.rs:109 #B3180-B3190)"
+                  },
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:109 #B3214-B3217)"
+                  ],
                   "format": "Str"
                 }
               ]
@@ -169,26 +458,78 @@ fn test_generate() {
           }
         },
         {
-          "id": "ActionResult",
-          "id_location": "L(derive-codegen/src/test.rs:84 #B2183-B2195)",
+          "id": "Status",
+          "id_location": "L(derive-codegen/src/test.rs:120 #B3316-B3322)",
           "rust_docs": null,
           "codegen_attrs": {
             "tags": [
-              "docs",
-              "L(derive-codegen/src/test.rs:84 #B2167-B2173)"
+              "rust-attrs",
+              "L(derive-codegen/src/test.rs:120 #B3266-B3278)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "rust-attrs",
+                "L(derive-codegen/src/test.rs:120 #B3266-B3278)"
+              ]
             ]
           },
+          "rust_non_exhaustive": "L(derive-codegen/src/test.rs:120 #B3295-B3309)",
+          "rust_must_use": [
+            null,
+            "L(derive-codegen/src/test.rs:120 #B3283-B3291)"
+          ],
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:120 #B3316-B3322)"
+          ],
           "container_kind": {
-            "Struct": {
-              "fields": [
+            "Enum": {
+              "repr": "External",
+              "variants": [
                 {
-                  "id": "result",
-                  "id_location": "L(derive-codegen/src/test.rs:84 #B2202-B2208)",
+                  "id": "Active",
+                  "id_location": "L(derive-codegen/src/test.rs:120 #B3329-B3335)",
+                  "index": 0,
+                  "discriminant": 0,
                   "rust_docs": null,
-                  "format": {
-                    "TypeName": {
-                      "ident": "Result_OkBasicallyOther_ErrStr",
-                      "generics": []
+                  "variant_format": "Unit"
+                },
+                {
+                  "id": "Paused",
+                  "id_location": "L(derive-codegen/src/test.rs:120 #B3359-B3365)",
+                  "index": 1,
+                  "discriminant": 1,
+                  "rust_docs": null,
+                  "rust_deprecated": {
+                    "since": null,
+                    "note": null,
+                    "location": "L(derive-codegen/src/test.rs:120 #B3343-B3353)"
+                  },
+                  "variant_format": "Unit"
+                },
+                {
+                  "id": "Failed",
+                  "id_location": "L(derive-codegen/src/test.rs:120 #B3393-B3399)",
+                  "index": 2,
+                  "discriminant": 2,
+                  "rust_docs": null,
+                  "rust_non_exhaustive": "L(derive-codegen/src/test.rs:120 #B3373-B3387)",
+                  "variant_format": {
+                    "Struct": {
+                      "fields": [
+                        {
+                          "id": "reason",
+                          "id_location": "L(derive-codegen/src/test.rs:120 #B3410-B3416)",
+                          "rust_docs": null,
+                          "rust_visibility": [
+                            "",
+                            "L(derive-codegen/src/test.rs:120 #B3410-B3416)"
+                          ],
+                          "format": "Str"
+                        }
+                      ]
                     }
                   }
                 }
@@ -197,39 +538,3165 @@ fn test_generate() {
           }
         },
         {
-          "id": "Result_OkBasicallyOther_ErrStr",
-          "id_location": "L(derive-codegen/src/test.rs:84 #B2210-B2216)",
-          "rust_docs": "`Result` is a type that represents either success ([`Ok`]) or failure ([`Err`]).",
+          "id": "Basic",
+          "id_location": "L(derive-codegen/src/test.rs:14 #B1047-B1052)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "fsharp",
+              "L(derive-codegen/src/test.rs:14 #B1029-B1037)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "fsharp",
+                "L(derive-codegen/src/test.rs:14 #B1029-B1037)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:14 #B1047-B1052)"
+          ],
           "container_kind": {
-            "Enum": {
-              "repr": "External",
-              "variants": [
+            "Struct": {
+              "fields": [
                 {
-                  "id": "Ok",
-                  "id_location": "L(derive-codegen/src/test.rs:84 #B2210-B2216)",
-                  "rust_docs": "Contains the success value",
-                  "variant_format": {
-                    "NewType": {
-                      "TypeName": {
-                        "ident": "BasicallyOther",
-                        "generics": []
-                      }
-                    }
-                  }
+                  "id": "a",
+                  "id_location": "L(derive-codegen/src/test.rs:14 #B1059-B1060)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:14 #B1059-B1060)"
+                  ],
+                  "format": "I32"
                 },
                 {
-                  "id": "Err",
-                  "id_location": "L(derive-codegen/src/test.rs:84 #B2210-B2216)",
-                  "rust_docs": "Contains the error value",
-                  "variant_format": {
-                    "NewType": "Str"
+                  "id": "b",
+                  "id_location": "L(derive-codegen/src/test.rs:14 #B1071-B1072)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:14 #B1071-B1072)"
+                  ],
+                  "format": "Str"
+                }
+              ]
+            }
+          },
+          "included_as_dependency": true
+        }
+      ],
+      "functions": [
+        {
+          "id": "check_status",
+          "id_location": "L(derive-codegen/src/test.rs:134 #B3597-B3609)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "rust-attrs",
+              "L(derive-codegen/src/test.rs:134 #B3503-B3515)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "rust-attrs",
+                "L(derive-codegen/src/test.rs:134 #B3503-B3515)"
+              ]
+            ]
+          },
+          "rust_deprecated": {
+            "since": "0.2.0",
+            "note": null,
+            "location": "L(derive-codegen/src/test.rs:134 #B3520-B3530)"
+          },
+          "rust_must_use": [
+            "the status should be checked",
+            "L(derive-codegen/src/test.rs:134 #B3551-B3559)"
+          ],
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:134 #B3597-B3609)"
+          ],
+          "function": {
+            "is_async": false,
+            "self_opt": null,
+            "receiver": null,
+            "params": [
+              {
+                "id": "basic",
+                "id_location": "L(derive-codegen/src/test.rs:134 #B3610-B3615)",
+                "rust_docs": null,
+                "format": {
+                  "TypeName": {
+                    "ident": "Basic",
+                    "generics": []
                   }
                 }
+              }
+            ],
+            "return_type": {
+              "TypeName": {
+                "ident": "Status",
+                "generics": []
+              }
+            }
+          }
+        }
+      ],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+#[doc = include_str!("test/included_docs.md")]
+#[derive(Codegen)]
+#[codegen(tags = "doc-links")]
+struct Included;
+
+/// Summary of [`Included`] items, see [`Summary::count`] and [Missing].
+///
+/// More in [the serde docs](https://serde.rs)
+/// and [`Included`][included], but not `[code]`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let summary = Summary { count: 1 };
+/// ```
+///
+/// ## Errors
+///
+/// Never.
+///
+/// [included]: crate::test::Included
+#[derive(Codegen)]
+#[codegen(tags = "doc-links")]
+struct Summary {
+    /// How many [Included] there are
+    count: usize,
+}
+
+#[test]
+fn test_doc_links() {
+    insta::assert_snapshot!(Generation::for_tag("doc-links").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "Summary",
+          "id_location": "L(derive-codegen/src/test.rs:679 #B20891-B20898)",
+          "rust_docs": "Summary of [`Included`] items, see [`Summary::count`] and [Missing].\n\nMore in [the serde docs](https://serde.rs)\nand [`Included`][included], but not `[code]`.\n\n# Examples\n\n```rust,ignore\nlet summary = Summary { count: 1 };\n```\n\n## Errors\n\nNever.\n\n[included]: crate::test::Included",
+          "rust_docs_parsed": {
+            "summary": "Summary of [`Included`] items, see [`Summary::count`] and [Missing].",
+            "body": "More in [the serde docs](https://serde.rs)\nand [`Included`][included], but not `[code]`.",
+            "sections": [
+              {
+                "heading": "Examples",
+                "level": 1,
+                "content": "```rust,ignore\nlet summary = Summary { count: 1 };\n```"
+              },
+              {
+                "heading": "Errors",
+                "level": 2,
+                "content": "Never.\n\n[included]: crate::test::Included"
+              }
+            ],
+            "code_blocks": [
+              {
+                "lang": "rust",
+                "code": "let summary = Summary { count: 1 };"
+              }
+            ],
+            "links": [
+              {
+                "text": "`Included`",
+                "target": "`Included`",
+                "id": "Included",
+                "member": null
+              },
+              {
+                "text": "`Summary::count`",
+                "target": "`Summary::count`",
+                "id": "Summary",
+                "member": "count"
+              },
+              {
+                "text": "Missing",
+                "target": "Missing",
+                "id": null,
+                "member": null
+              },
+              {
+                "text": "the serde docs",
+                "target": "https://serde.rs",
+                "id": null,
+                "member": null
+              },
+              {
+                "text": "`Included`",
+                "target": "crate::test::Included",
+                "id": "Included",
+                "member": null
+              }
+            ]
+          },
+          "codegen_attrs": {
+            "tags": [
+              "doc-links",
+              "L(derive-codegen/src/test.rs:679 #B20870-B20881)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "doc-links",
+                "L(derive-codegen/src/test.rs:679 #B20870-B20881)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:679 #B20891-B20898)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "count",
+                  "id_location": "L(derive-codegen/src/test.rs:679 #B20943-B20948)",
+                  "rust_docs": "How many [Included] there are",
+                  "rust_docs_parsed": {
+                    "summary": "How many [Included] there are",
+                    "links": [
+                      {
+                        "text": "Included",
+                        "target": "Included",
+                        "id": "Included",
+                        "member": null
+                      }
+                    ]
+                  },
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:679 #B20943-B20948)"
+                  ],
+                  "format": "USIZE"
+                }
+              ]
+            }
+          }
+        },
+        {
+          "id": "Included",
+          "id_location": "L(derive-codegen/src/test.rs:659 #B20484-B20492)",
+          "rust_docs": "Included from a markdown file, with a link to [Summary].",
+          "rust_docs_parsed": {
+            "summary": "Included from a markdown file, with a link to [Summary].",
+            "links": [
+              {
+                "text": "Summary",
+                "target": "Summary",
+                "id": "Summary",
+                "member": null
+              }
+            ]
+          },
+          "codegen_attrs": {
+            "tags": [
+              "doc-links",
+              "L(derive-codegen/src/test.rs:659 #B20463-B20474)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "doc-links",
+                "L(derive-codegen/src/test.rs:659 #B20463-B20474)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:659 #B20484-B20492)"
+          ],
+          "container_kind": "UnitStruct"
+        }
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+#[derive(Codegen, Deserialize)]
+#[codegen(tags = "repeated-attrs")]
+#[codegen(tags = " repeated-attrs-other , ")]
+struct Repeated {
+    #[serde(alias = "a", alias = "b")]
+    #[serde(rename = "value")]
+    #[codegen(ts_as = "string", ts_as = "number")]
+    field: usize,
+}
+
+#[test]
+fn test_repeated_attrs() {
+    insta::assert_snapshot!(Generation::for_tag("repeated-attrs").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "Repeated",
+          "id_location": "L(derive-codegen/src/test.rs:841 #B26124-B26132)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              " repeated-attrs-other , ",
+              "L(derive-codegen/src/test.rs:841 #B26088-B26114)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "repeated-attrs",
+                "L(derive-codegen/src/test.rs:841 #B26052-B26068)"
+              ],
+              [
+                " repeated-attrs-other , ",
+                "L(derive-codegen/src/test.rs:841 #B26088-B26114)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:841 #B26124-B26132)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "field",
+                  "id_location": "L(derive-codegen/src/test.rs:841 #B26260-B26265)",
+                  "rust_docs": null,
+                  "serde_attrs": {
+                    "alias": [
+                      "b",
+                      "L(derive-codegen/src/test.rs:841 #B26168-B26171)"
+                    ],
+                    "rename": [
+                      "value",
+                      "L(derive-codegen/src/test.rs:841 #B26195-B26202)"
+                    ]
+                  },
+                  "serde_attrs_repeated": {
+                    "alias": [
+                      [
+                        "a",
+                        "L(derive-codegen/src/test.rs:841 #B26155-B26158)"
+                      ],
+                      [
+                        "b",
+                        "L(derive-codegen/src/test.rs:841 #B26168-B26171)"
+                      ]
+                    ],
+                    "rename": [
+                      [
+                        "value",
+                        "L(derive-codegen/src/test.rs:841 #B26195-B26202)"
+                      ]
+                    ]
+                  },
+                  "serde_meta": [
+                    {
+                      "key": "alias",
+                      "key_location": "L(derive-codegen/src/test.rs:841 #B26147-B26152)",
+                      "value": {
+                        "Lit": {
+                          "value": "a",
+                          "location": "L(derive-codegen/src/test.rs:841 #B26155-B26158)"
+                        }
+                      }
+                    },
+                    {
+                      "key": "alias",
+                      "key_location": "L(derive-codegen/src/test.rs:841 #B26160-B26165)",
+                      "value": {
+                        "Lit": {
+                          "value": "b",
+                          "location": "L(derive-codegen/src/test.rs:841 #B26168-B26171)"
+                        }
+                      }
+                    },
+                    {
+                      "key": "rename",
+                      "key_location": "L(derive-codegen/src/test.rs:841 #B26186-B26192)",
+                      "value": {
+                        "Lit": {
+                          "value": "value",
+                          "location": "L(derive-codegen/src/test.rs:841 #B26195-B26202)"
+                        }
+                      }
+                    }
+                  ],
+                  "codegen_attrs": {
+                    "ts_as": [
+                      "number",
+                      "L(derive-codegen/src/test.rs:841 #B26245-B26253)"
+                    ]
+                  },
+                  "codegen_attrs_repeated": {
+                    "ts_as": [
+                      [
+                        "string",
+                        "L(derive-codegen/src/test.rs:841 #B26227-B26235)"
+                      ],
+                      [
+                        "number",
+                        "L(derive-codegen/src/test.rs:841 #B26245-B26253)"
+                      ]
+                    ]
+                  },
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:841 #B26260-B26265)"
+                  ],
+                  "format": "USIZE"
+                }
+              ]
+            }
+          }
+        }
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+    assert_eq!(
+        Generation::for_tag("repeated-attrs-other").to_input_json(),
+        Generation::for_tag("repeated-attrs").to_input_json(),
+    );
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "capture-default", capture_default)]
+#[serde(default)]
+struct Settings {
+    name: String,
+    #[serde(default = "default_retries")]
+    retries: u32,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            name: "settings".to_string(),
+            retries: 1,
+            labels: vec!["a".to_string()],
+        }
+    }
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+#[derive(Codegen, Serialize, Deserialize, Default)]
+#[codegen(tags = "capture-default", capture_default)]
+enum Mode {
+    #[default]
+    Fast,
+    Slow {
+        #[serde(default = "default_retries")]
+        retries: u32,
+        delay: u32,
+    },
+}
+
+/// e.g. `Settings.retries: 3`
+fn captured_field_defaults(path: &str, fields: &serde_json::Value) -> Vec<String> {
+    fields
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|field| {
+            let value = field.get("captured_default")?;
+            Some(format!("{path}.{}: {value}", field["id"].as_str().unwrap()))
+        })
+        .collect()
+}
+
+#[test]
+fn test_capture_default() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("capture-default").to_input_json()).unwrap();
+    let mut captured = Vec::new();
+    let mut declarations = input["declarations"].as_array().unwrap().clone();
+    declarations.sort_by_key(|decl| decl["id"].as_str().unwrap().to_string());
+    for decl in declarations {
+        let id = decl["id"].as_str().unwrap();
+        captured.push(format!("{id}: {}", decl["captured_default"]));
+        captured.extend(captured_field_defaults(
+            id,
+            &decl["container_kind"]["Struct"]["fields"],
+        ));
+        for variant in decl["container_kind"]["Enum"]["variants"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            captured.extend(captured_field_defaults(
+                &format!("{id}::{}", variant["id"].as_str().unwrap()),
+                &variant["variant_format"]["Struct"]["fields"],
+            ));
+        }
+    }
+    insta::assert_snapshot!(captured.join("\n"), @r###"
+    Mode: "Fast"
+    Mode::Slow.retries: 3
+    Settings: {"labels":["a"],"name":"settings","retries":1}
+    Settings.retries: 3
+    Settings.labels: []
+    "###);
+}
+
+/// The most items in a page
+#[i_codegen_derive::const_codegen]
+#[i_codegen_derive::codegen(tags = "constants")]
+pub const MAX_ITEMS: u32 = 100;
+
+#[i_codegen_derive::const_codegen]
+#[i_codegen_derive::codegen(tags = "constants")]
+static FEATURES: &[&str] = &["search", "export"];
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "constants")]
+struct Limit {
+    code: u16,
+    message: &'static str,
+}
+
+#[i_codegen_derive::const_codegen]
+#[i_codegen_derive::codegen(tags = "constants")]
+const LIMITS: [Limit; 1] = [Limit {
+    code: 429,
+    message: "Too many requests",
+}];
+
+#[test]
+fn test_constants() {
+    insta::assert_snapshot!(Generation::for_tag("constants").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "Limit",
+          "id_location": "L(derive-codegen/src/test.rs:1085 #B33773-B33778)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "constants",
+              "L(derive-codegen/src/test.rs:1085 #B33752-B33763)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "constants",
+                "L(derive-codegen/src/test.rs:1085 #B33752-B33763)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1085 #B33773-B33778)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "code",
+                  "id_location": "L(derive-codegen/src/test.rs:1085 #B33785-B33789)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:1085 #B33785-B33789)"
+                  ],
+                  "format": "U16"
+                },
+                {
+                  "id": "message",
+                  "id_location": "L(derive-codegen/src/test.rs:1085 #B33800-B33807)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:1085 #B33800-B33807)"
+                  ],
+                  "format": "Str"
+                }
+              ]
+            }
+          }
+        }
+      ],
+      "functions": [],
+      "constants": [
+        {
+          "id": "LIMITS",
+          "id_location": "L(derive-codegen/src/test.rs:1092 #B33916-B33922)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "constants",
+              "L(derive-codegen/src/test.rs:1092 #B33896-B33907)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "constants",
+                "L(derive-codegen/src/test.rs:1092 #B33896-B33907)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1092 #B33916-B33922)"
+          ],
+          "is_static": false,
+          "format": {
+            "Seq": {
+              "TypeName": {
+                "ident": "Limit",
+                "generics": []
+              }
+            }
+          },
+          "value": [
+            {
+              "code": 429,
+              "message": "Too many requests"
+            }
+          ]
+        },
+        {
+          "id": "FEATURES",
+          "id_location": "L(derive-codegen/src/test.rs:1081 #B33661-B33669)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "constants",
+              "L(derive-codegen/src/test.rs:1081 #B33640-B33651)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "constants",
+                "L(derive-codegen/src/test.rs:1081 #B33640-B33651)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1081 #B33661-B33669)"
+          ],
+          "is_static": true,
+          "format": {
+            "Seq": "Str"
+          },
+          "value": [
+            "search",
+            "export"
+          ]
+        },
+        {
+          "id": "MAX_ITEMS",
+          "id_location": "L(derive-codegen/src/test.rs:1077 #B33547-B33556)",
+          "rust_docs": "The most items in a page",
+          "rust_docs_parsed": {
+            "summary": "The most items in a page"
+          },
+          "codegen_attrs": {
+            "tags": [
+              "constants",
+              "L(derive-codegen/src/test.rs:1077 #B33523-B33534)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "constants",
+                "L(derive-codegen/src/test.rs:1077 #B33523-B33534)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "pub",
+            "L(derive-codegen/src/test.rs:1077 #B33537-B33540)"
+          ],
+          "is_static": false,
+          "format": "U32",
+          "value": 100
+        }
+      ],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+/// Identifies a user
+#[i_codegen_derive::codegen(tags = "aliases")]
+pub type UserId = String;
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "aliases")]
+struct Paginated<T, const N: usize> {
+    items: Vec<T>,
+}
+
+#[i_codegen_derive::codegen(tags = "aliases")]
+#[i_codegen_derive::codegen(ts_as = "Page")]
+type Page<T> = Paginated<T, 50>;
+
+#[test]
+fn test_aliases() {
+    insta::assert_snapshot!(Generation::for_tag("aliases").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "Paginated",
+          "id_location": "L(derive-codegen/src/test.rs:1267 #B38743-B38752)",
+          "rust_docs": null,
+          "rust_generics": [
+            [
+              "T",
+              "L(derive-codegen/src/test.rs:1267 #B38753-B38754)"
+            ]
+          ],
+          "codegen_attrs": {
+            "tags": [
+              "aliases",
+              "L(derive-codegen/src/test.rs:1267 #B38724-B38733)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "aliases",
+                "L(derive-codegen/src/test.rs:1267 #B38724-B38733)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1267 #B38743-B38752)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "items",
+                  "id_location": "L(derive-codegen/src/test.rs:1267 #B38778-B38783)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:1267 #B38778-B38783)"
+                  ],
+                  "format": {
+                    "Seq": {
+                      "TypeName": {
+                        "ident": "T",
+                        "generics": []
+                      }
+                    }
+                  }
+                }
+              ]
+            }
+          }
+        }
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [
+        {
+          "id": "Page",
+          "id_location": "L(derive-codegen/src/test.rs:1273 #B38893-B38897)",
+          "rust_docs": null,
+          "rust_generics": [
+            [
+              "T",
+              "L(derive-codegen/src/test.rs:1273 #B38898-B38899)"
+            ]
+          ],
+          "codegen_attrs": {
+            "tags": [
+              "aliases",
+              "L(derive-codegen/src/test.rs:1273 #B38831-B38840)"
+            ],
+            "ts_as": [
+              "Page",
+              "L(derive-codegen/src/test.rs:1273 #B38879-B38885)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "aliases",
+                "L(derive-codegen/src/test.rs:1273 #B38831-B38840)"
+              ]
+            ],
+            "ts_as": [
+              [
+                "Page",
+                "L(derive-codegen/src/test.rs:1273 #B38879-B38885)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1273 #B38893-B38897)"
+          ],
+          "target": {
+            "TypeName": {
+              "ident": "Paginated",
+              "generics": [
+                {
+                  "TypeName": {
+                    "ident": "T",
+                    "generics": []
+                  }
+                }
+              ]
+            }
+          }
+        },
+        {
+          "id": "UserId",
+          "id_location": "L(derive-codegen/src/test.rs:1264 #B38659-B38665)",
+          "rust_docs": "Identifies a user",
+          "rust_docs_parsed": {
+            "summary": "Identifies a user"
+          },
+          "codegen_attrs": {
+            "tags": [
+              "aliases",
+              "L(derive-codegen/src/test.rs:1264 #B38638-B38647)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "aliases",
+                "L(derive-codegen/src/test.rs:1264 #B38638-B38647)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "pub",
+            "L(derive-codegen/src/test.rs:1264 #B38650-B38653)"
+          ],
+          "target": "Str"
+        }
+      ],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "services")]
+pub struct UserStore {
+    users: Vec<String>,
+}
+
+/// Looks up users
+#[i_codegen_derive::codegen(tags = "services")]
+impl UserStore {
+    /// Finds a user by name
+    pub fn find(&self, name: String) -> Option<String> {
+        None
+    }
+    pub fn add(&mut self, name: String) {}
+    #[allow(clippy::boxed_local)]
+    pub fn boxed(self: Box<Self>) -> Self {
+        *self
+    }
+    pub async fn new() -> Self {
+        UserStore { users: Vec::new() }
+    }
+    #[i_codegen_derive::codegen(internal)]
+    fn reindex(self) -> Vec<Self> {
+        vec![self]
+    }
+    fn private(&self) {}
+}
+
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+#[i_codegen_derive::codegen(tags = "services")]
+impl Describe for UserStore {
+    fn describe(&self) -> String {
+        self.users.join(", ")
+    }
+}
+
+#[test]
+fn test_services() {
+    insta::assert_snapshot!(Generation::for_tag("services").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "UserStore",
+          "id_location": "L(derive-codegen/src/test.rs:1425 #B43044-B43053)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "services",
+              "L(derive-codegen/src/test.rs:1425 #B43020-B43030)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "services",
+                "L(derive-codegen/src/test.rs:1425 #B43020-B43030)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "pub",
+            "L(derive-codegen/src/test.rs:1425 #B43033-B43036)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "users",
+                  "id_location": "L(derive-codegen/src/test.rs:1425 #B43060-B43065)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:1425 #B43060-B43065)"
+                  ],
+                  "format": {
+                    "Seq": "Str"
+                  }
+                }
+              ]
+            }
+          }
+        }
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [],
+      "services": [
+        {
+          "id": "UserStoreImpl",
+          "id_location": "L(derive-codegen/src/test.rs:1432 #B43155-B43164)",
+          "rust_docs": "Looks up users",
+          "rust_docs_parsed": {
+            "summary": "Looks up users"
+          },
+          "codegen_attrs": {
+            "tags": [
+              "services",
+              "L(derive-codegen/src/test.rs:1432 #B43137-B43147)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "services",
+                "L(derive-codegen/src/test.rs:1432 #B43137-B43147)"
+              ]
+            ]
+          },
+          "self_type": {
+            "TypeName": {
+              "ident": "UserStore",
+              "generics": []
+            }
+          },
+          "trait_type": null,
+          "methods": [
+            {
+              "id": "find",
+              "id_location": "L(derive-codegen/src/test.rs:1432 #B43207-B43211)",
+              "rust_docs": "Finds a user by name",
+              "rust_docs_parsed": {
+                "summary": "Finds a user by name"
+              },
+              "rust_visibility": [
+                "pub",
+                "L(derive-codegen/src/test.rs:1432 #B43200-B43203)"
+              ],
+              "function": {
+                "is_async": false,
+                "self_opt": {
+                  "id": "self",
+                  "id_location": "L(derive-codegen/src/test.rs:1432 #B43213-B43217)",
+                  "rust_docs": null,
+                  "format": {
+                    "TypeName": {
+                      "ident": "UserStore",
+                      "generics": []
+                    }
+                  }
+                },
+                "receiver": "Ref",
+                "params": [
+                  {
+                    "id": "name",
+                    "id_location": "L(derive-codegen/src/test.rs:1432 #B43219-B43223)",
+                    "rust_docs": null,
+                    "format": "Str"
+                  }
+                ],
+                "return_type": {
+                  "Option": "Str"
+                }
+              }
+            },
+            {
+              "id": "add",
+              "id_location": "L(derive-codegen/src/test.rs:1432 #B43283-B43286)",
+              "rust_docs": null,
+              "rust_visibility": [
+                "pub",
+                "L(derive-codegen/src/test.rs:1432 #B43276-B43279)"
+              ],
+              "function": {
+                "is_async": false,
+                "self_opt": {
+                  "id": "self",
+                  "id_location": "L(derive-codegen/src/test.rs:1432 #B43292-B43296)",
+                  "rust_docs": null,
+                  "format": {
+                    "TypeName": {
+                      "ident": "UserStore",
+                      "generics": []
+                    }
+                  }
+                },
+                "receiver": "RefMut",
+                "params": [
+                  {
+                    "id": "name",
+                    "id_location": "L(derive-codegen/src/test.rs:1432 #B43298-B43302)",
+                    "rust_docs": null,
+                    "format": "Str"
+                  }
+                ],
+                "return_type": "Unit"
+              }
+            },
+            {
+              "id": "boxed",
+              "id_location": "L(derive-codegen/src/test.rs:1432 #B43360-B43365)",
+              "rust_docs": null,
+              "rust_visibility": [
+                "pub",
+                "L(derive-codegen/src/test.rs:1432 #B43353-B43356)"
+              ],
+              "function": {
+                "is_async": false,
+                "self_opt": {
+                  "id": "self",
+                  "id_location": "L(derive-codegen/src/test.rs:1432 #B43366-B43370)",
+                  "rust_docs": null,
+                  "format": {
+                    "TypeName": {
+                      "ident": "UserStore",
+                      "generics": []
+                    }
+                  }
+                },
+                "receiver": "Typed",
+                "params": [],
+                "return_type": {
+                  "TypeName": {
+                    "ident": "UserStore",
+                    "generics": []
+                  }
+                }
+              }
+            },
+            {
+              "id": "new",
+              "id_location": "L(derive-codegen/src/test.rs:1432 #B43430-B43433)",
+              "rust_docs": null,
+              "rust_visibility": [
+                "pub",
+                "L(derive-codegen/src/test.rs:1432 #B43417-B43420)"
+              ],
+              "function": {
+                "is_async": true,
+                "self_opt": null,
+                "receiver": null,
+                "params": [],
+                "return_type": {
+                  "TypeName": {
+                    "ident": "UserStore",
+                    "generics": []
+                  }
+                }
+              }
+            },
+            {
+              "id": "reindex",
+              "id_location": "L(derive-codegen/src/test.rs:1432 #B43542-B43549)",
+              "rust_docs": null,
+              "codegen_flags": {
+                "internal": "L(derive-codegen/src/test.rs:1432 #B43532-B43533)"
+              },
+              "rust_visibility": [
+                "",
+                "L(derive-codegen/src/test.rs:1432 #B43542-B43549)"
+              ],
+              "function": {
+                "is_async": false,
+                "self_opt": {
+                  "id": "self",
+                  "id_location": "L(derive-codegen/src/test.rs:1432 #B43550-B43554)",
+                  "rust_docs": null,
+                  "format": {
+                    "TypeName": {
+                      "ident": "UserStore",
+                      "generics": []
+                    }
+                  }
+                },
+                "receiver": "Owned",
+                "params": [],
+                "return_type": {
+                  "Seq": {
+                    "TypeName": {
+                      "ident": "UserStore",
+                      "generics": []
+                    }
+                  }
+                }
+              }
+            }
+          ]
+        },
+        {
+          "id": "UserStoreDescribeImpl",
+          "id_location": "L(derive-codegen/src/test.rs:1457 #B43744-B43753)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "services",
+              "L(derive-codegen/src/test.rs:1457 #B43713-B43723)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "services",
+                "L(derive-codegen/src/test.rs:1457 #B43713-B43723)"
+              ]
+            ]
+          },
+          "self_type": {
+            "TypeName": {
+              "ident": "UserStore",
+              "generics": []
+            }
+          },
+          "trait_type": {
+            "TypeName": {
+              "ident": "Describe",
+              "generics": []
+            }
+          },
+          "methods": [
+            {
+              "id": "describe",
+              "id_location": "L(derive-codegen/src/test.rs:1457 #B43763-B43771)",
+              "rust_docs": null,
+              "rust_visibility": [
+                "",
+                "L(derive-codegen/src/test.rs:1457 #B43763-B43771)"
+              ],
+              "function": {
+                "is_async": false,
+                "self_opt": {
+                  "id": "self",
+                  "id_location": "L(derive-codegen/src/test.rs:1457 #B43773-B43777)",
+                  "rust_docs": null,
+                  "format": {
+                    "TypeName": {
+                      "ident": "UserStore",
+                      "generics": []
+                    }
+                  }
+                },
+                "receiver": "Ref",
+                "params": [],
+                "return_type": "Str"
+              }
+            }
+          ]
+        }
+      ],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+/// Manages users
+#[i_codegen_derive::codegen(tags = "interfaces")]
+trait UserApi {
+    /// Gets a user by id
+    async fn get(&self, id: usize) -> Result<String, u16>;
+    #[i_codegen_derive::codegen(idempotent)]
+    fn remove(&mut self, id: usize) -> bool {
+        false
+    }
+    fn create() -> Self;
+}
+
+#[test]
+fn test_interfaces() {
+    insta::assert_snapshot!(Generation::for_tag("interfaces").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "Result_OkStr_ErrU16",
+          "id_location": "L(derive-codegen/src/test.rs:1772 #B53506-B53512)",
+          "rust_docs": "`Result` is a type that represents either success ([`Ok`]) or failure ([`Err`]).",
+          "rust_docs_parsed": {
+            "summary": "`Result` is a type that represents either success ([`Ok`]) or failure ([`Err`]).",
+            "links": [
+              {
+                "text": "`Ok`",
+                "target": "`Ok`",
+                "id": null,
+                "member": null
+              },
+              {
+                "text": "`Err`",
+                "target": "`Err`",
+                "id": null,
+                "member": null
+              }
+            ]
+          },
+          "container_kind": {
+            "Enum": {
+              "repr": "External",
+              "variants": [
+                {
+                  "id": "Ok",
+                  "id_location": "L(derive-codegen/src/test.rs:1772 #B53506-B53512)",
+                  "index": 0,
+                  "rust_docs": "Contains the success value",
+                  "rust_docs_parsed": {
+                    "summary": "Contains the success value"
+                  },
+                  "variant_format": {
+                    "NewType": "Str"
+                  }
+                },
+                {
+                  "id": "Err",
+                  "id_location": "L(derive-codegen/src/test.rs:1772 #B53506-B53512)",
+                  "index": 1,
+                  "rust_docs": "Contains the error value",
+                  "rust_docs_parsed": {
+                    "summary": "Contains the error value"
+                  },
+                  "variant_format": {
+                    "NewType": "U16"
+                  }
+                }
+              ]
+            }
+          }
+        }
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [
+        {
+          "id": "UserApi",
+          "id_location": "L(derive-codegen/src/test.rs:1772 #B53432-B53439)",
+          "rust_docs": "Manages users",
+          "rust_docs_parsed": {
+            "summary": "Manages users"
+          },
+          "codegen_attrs": {
+            "tags": [
+              "interfaces",
+              "L(derive-codegen/src/test.rs:1772 #B53411-B53423)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "interfaces",
+                "L(derive-codegen/src/test.rs:1772 #B53411-B53423)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1772 #B53432-B53439)"
+          ],
+          "methods": [
+            {
+              "id": "get",
+              "id_location": "L(derive-codegen/src/test.rs:1772 #B53481-B53484)",
+              "rust_docs": "Gets a user by id",
+              "rust_docs_parsed": {
+                "summary": "Gets a user by id"
+              },
+              "function": {
+                "is_async": true,
+                "self_opt": {
+                  "id": "self",
+                  "id_location": "L(derive-codegen/src/test.rs:1772 #B53486-B53490)",
+                  "rust_docs": null,
+                  "format": {
+                    "TypeName": {
+                      "ident": "Self",
+                      "generics": []
+                    }
+                  }
+                },
+                "receiver": "Ref",
+                "params": [
+                  {
+                    "id": "id",
+                    "id_location": "L(derive-codegen/src/test.rs:1772 #B53492-B53494)",
+                    "rust_docs": null,
+                    "format": "USIZE"
+                  }
+                ],
+                "return_type": {
+                  "TypeName": {
+                    "ident": "Result_OkStr_ErrU16",
+                    "generics": []
+                  }
+                },
+                "return_ok": "Str",
+                "return_err": "U16"
+              }
+            },
+            {
+              "id": "remove",
+              "id_location": "L(derive-codegen/src/test.rs:1772 #B53579-B53585)",
+              "rust_docs": null,
+              "codegen_flags": {
+                "idempotent": "L(derive-codegen/src/test.rs:1772 #B53569-B53570)"
+              },
+              "function": {
+                "is_async": false,
+                "self_opt": {
+                  "id": "self",
+                  "id_location": "L(derive-codegen/src/test.rs:1772 #B53591-B53595)",
+                  "rust_docs": null,
+                  "format": {
+                    "TypeName": {
+                      "ident": "Self",
+                      "generics": []
+                    }
+                  }
+                },
+                "receiver": "RefMut",
+                "params": [
+                  {
+                    "id": "id",
+                    "id_location": "L(derive-codegen/src/test.rs:1772 #B53597-B53599)",
+                    "rust_docs": null,
+                    "format": "USIZE"
+                  }
+                ],
+                "return_type": "Bool"
+              }
+            },
+            {
+              "id": "create",
+              "id_location": "L(derive-codegen/src/test.rs:1772 #B53645-B53651)",
+              "rust_docs": null,
+              "function": {
+                "is_async": false,
+                "self_opt": null,
+                "receiver": null,
+                "params": [],
+                "return_type": {
+                  "TypeName": {
+                    "ident": "Self",
+                    "generics": []
+                  }
+                }
+              }
+            }
+          ]
+        }
+      ],
+      "operations": []
+    }
+    "###);
+}
+
+#[i_codegen_derive::fn_codegen(tag = "fn-args", internal)]
+fn tagged_by_args(id: usize) {}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-args, fn-args-other", rename = "renamed")]
+#[i_codegen_derive::codegen(hidden)]
+fn tagged_by_args_and_attrs() {}
+
+#[test]
+fn test_fn_codegen_args() {
+    insta::assert_snapshot!(Generation::for_tag("fn-args").to_input_json_pretty(), @r###"
+    {
+      "declarations": [],
+      "functions": [
+        {
+          "id": "tagged_by_args",
+          "id_location": "L(derive-codegen/src/test.rs:1969 #B59661-B59675)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tag": [
+              "fn-args",
+              "L(derive-codegen/src/test.rs:1969 #B59636-B59645)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tag": [
+              [
+                "fn-args",
+                "L(derive-codegen/src/test.rs:1969 #B59636-B59645)"
+              ]
+            ]
+          },
+          "codegen_flags": {
+            "internal": "L(derive-codegen/src/test.rs:1969 #B59599-B59657)"
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1969 #B59661-B59675)"
+          ],
+          "function": {
+            "is_async": false,
+            "self_opt": null,
+            "receiver": null,
+            "params": [
+              {
+                "id": "id",
+                "id_location": "L(derive-codegen/src/test.rs:1969 #B59676-B59678)",
+                "rust_docs": null,
+                "format": "USIZE"
+              }
+            ],
+            "return_type": "Unit"
+          }
+        },
+        {
+          "id": "tagged_by_args_and_attrs",
+          "id_location": "L(derive-codegen/src/test.rs:1972 #B59816-B59840)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "rename": [
+              "renamed",
+              "L(derive-codegen/src/test.rs:1972 #B59764-B59773)"
+            ],
+            "tags": [
+              "fn-args, fn-args-other",
+              "L(derive-codegen/src/test.rs:1972 #B59729-B59753)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "rename": [
+              [
+                "renamed",
+                "L(derive-codegen/src/test.rs:1972 #B59764-B59773)"
+              ]
+            ],
+            "tags": [
+              [
+                "fn-args, fn-args-other",
+                "L(derive-codegen/src/test.rs:1972 #B59729-B59753)"
+              ]
+            ]
+          },
+          "codegen_flags": {
+            "hidden": "L(derive-codegen/src/test.rs:1972 #B59810-B59811)"
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:1972 #B59816-B59840)"
+          ],
+          "function": {
+            "is_async": false,
+            "self_opt": null,
+            "receiver": null,
+            "params": [],
+            "return_type": "Unit"
+          }
+        }
+      ],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+    let other: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("fn-args-other").to_input_json()).unwrap();
+    assert_eq!(other["functions"][0]["id"], "tagged_by_args_and_attrs");
+    assert_eq!(other["functions"].as_array().unwrap().len(), 1);
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-generics")]
+fn first_matching<'a, T: Clone + Default, F>(query: &'a str, items: Vec<T>, matches: F) -> Option<T>
+where
+    F: Fn(&'a str, &T) -> bool,
+    Vec<T>: std::fmt::Debug,
+{
+    items.into_iter().find(|item| matches(query, item))
+}
+
+#[test]
+fn test_fn_generics() {
+    insta::assert_snapshot!(Generation::for_tag("fn-generics").to_input_json_pretty(), @r###"
+    {
+      "declarations": [],
+      "functions": [
+        {
+          "id": "first_matching",
+          "id_location": "L(derive-codegen/src/test.rs:2079 #B62997-B63011)",
+          "rust_docs": null,
+          "rust_generics": [
+            [
+              "T",
+              "L(derive-codegen/src/test.rs:2079 #B63016-B63017)"
+            ],
+            [
+              "F",
+              "L(derive-codegen/src/test.rs:2079 #B63036-B63037)"
+            ]
+          ],
+          "codegen_attrs": {
+            "tags": [
+              "fn-generics",
+              "L(derive-codegen/src/test.rs:2079 #B62978-B62991)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "fn-generics",
+                "L(derive-codegen/src/test.rs:2079 #B62978-B62991)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:2079 #B62997-B63011)"
+          ],
+          "function": {
+            "is_async": false,
+            "self_opt": null,
+            "receiver": null,
+            "params": [
+              {
+                "id": "query",
+                "id_location": "L(derive-codegen/src/test.rs:2079 #B63039-B63044)",
+                "rust_docs": null,
+                "format": "Str"
+              },
+              {
+                "id": "items",
+                "id_location": "L(derive-codegen/src/test.rs:2079 #B63055-B63060)",
+                "rust_docs": null,
+                "format": {
+                  "Seq": {
+                    "Generic": {
+                      "ident": "T"
+                    }
+                  }
+                }
+              },
+              {
+                "id": "matches",
+                "id_location": "L(derive-codegen/src/test.rs:2079 #B63070-B63077)",
+                "rust_docs": null,
+                "format": {
+                  "Generic": {
+                    "ident": "F"
+                  }
+                }
+              }
+            ],
+            "return_type": {
+              "Option": {
+                "Generic": {
+                  "ident": "T"
+                }
+              }
+            },
+            "bounds": [
+              {
+                "ident": "T",
+                "bounds": [
+                  "Clone",
+                  "Default"
+                ],
+                "location": "L(derive-codegen/src/test.rs:2079 #B63016-B63017)"
+              },
+              {
+                "ident": "F",
+                "bounds": [
+                  "Fn(&'a str, &T) -> bool"
+                ],
+                "location": "L(derive-codegen/src/test.rs:2079 #B63036-B63037)"
+              }
+            ],
+            "lifetimes": [
+              [
+                "'a",
+                "L(derive-codegen/src/test.rs:2079 #B63012-B63014)"
+              ]
+            ],
+            "where_predicates": [
+              [
+                "Vec<T>: std::fmt::Debug",
+                "L(derive-codegen/src/test.rs:2079 #B63137-B63140)"
+              ]
+            ]
+          }
+        }
+      ],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-results")]
+fn find_user(id: u32) -> Result<String, Mode> {
+    Err(Mode::Fast)
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-results", result_alias = "io::Result<io::Error>")]
+fn read_user(path: &str) -> std::io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+type AnyResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+#[i_codegen_derive::fn_codegen(tags = "fn-results", result_alias = "AnyResult")]
+fn load_user() -> AnyResult<Option<String>> {
+    Ok(None)
+}
+
+/// One line for each function, with its `return_type`, `return_ok` and `return_err`
+fn function_returns(input: &serde_json::Value) -> String {
+    input["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|function| {
+            format!(
+                "{}: {} ok: {} err: {}",
+                function["id"].as_str().unwrap(),
+                function["function"]["return_type"],
+                function["function"]["return_ok"],
+                function["function"]["return_err"]
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_fn_results() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("fn-results").to_input_json()).unwrap();
+    insta::assert_snapshot!(function_returns(&input), @r###"
+    find_user: {"TypeName":{"generics":[],"ident":"Result_OkStr_ErrMode"}} ok: "Str" err: {"TypeName":{"generics":[],"ident":"Mode"}}
+    load_user: {"TypeName":{"generics":[{"Option":"Str"}],"ident":"AnyResult"}} ok: {"Option":"Str"} err: null
+    read_user: {"TypeName":{"generics":["Str"],"ident":"Result"}} ok: "Str" err: {"TypeName":{"generics":[],"ident":"Error"}}
+    "###);
+}
+
+/// Stand-ins for the `futures` and `tokio` stream types
+mod streams {
+    pub trait Stream {
+        type Item;
+    }
+    pub type BoxStream<'a, T> = std::pin::Pin<Box<dyn Stream<Item = T> + Send + 'a>>;
+    /// Like `futures::stream::iter`
+    pub struct Iter<I>(pub I);
+    impl<I: Iterator> Stream for Iter<I> {
+        type Item = I::Item;
+    }
+    pub mod mpsc {
+        pub struct Receiver<T>(pub T);
+    }
+    pub mod oneshot {
+        pub struct Receiver<T>(pub T);
+    }
+}
+use streams::{mpsc, oneshot, BoxStream, Stream};
+
+#[i_codegen_derive::fn_codegen(tags = "fn-streams")]
+#[allow(clippy::manual_async_fn)]
+fn get_mode() -> impl std::future::Future<Output = Result<Mode, String>> {
+    async { Ok(Mode::Fast) }
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-streams")]
+fn watch_modes(from: Mode) -> std::pin::Pin<Box<dyn Stream<Item = Mode> + Send>> {
+    Box::pin(streams::Iter(std::iter::once(from)))
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-streams")]
+async fn subscribe(topic: String) -> mpsc::Receiver<u32> {
+    mpsc::Receiver(0)
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-streams")]
+fn upload<S>(name: String, chunks: S) -> oneshot::Receiver<u64>
+where
+    S: Stream<Item = Vec<u8>>,
+{
+    oneshot::Receiver(0)
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-streams")]
+fn echo(messages: impl Stream<Item = String>) -> BoxStream<'static, String> {
+    Box::pin(streams::Iter(std::iter::empty()))
+}
+
+/// Not a channel, even though it's named like one
+#[derive(Codegen)]
+#[codegen(tags = "fn-streams")]
+struct Receiver<T> {
+    item: T,
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-streams")]
+fn receipt() -> Receiver<u32> {
+    Receiver { item: 0 }
+}
+
+/// One line for each function, sorted by id, with whether it's async and its `call`
+fn function_calls(input: &serde_json::Value) -> String {
+    let mut lines = input["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|function| {
+            format!(
+                "{}: async: {} future_output: {} ok: {} call: {}",
+                function["id"].as_str().unwrap(),
+                function["function"]["is_async"],
+                function["function"]["return_future_output"],
+                function["function"]["return_ok"],
+                function["function"]["call"]
+            )
+        })
+        .collect::<Vec<_>>();
+    lines.sort();
+    lines.join("\n")
+}
+
+#[test]
+fn test_fn_streams() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("fn-streams").to_input_json()).unwrap();
+    insta::assert_snapshot!(function_calls(&input), @r###"
+    echo: async: false future_output: null ok: null call: {"Bidirectional":{"param":"messages","request":"Str","response":"Str"}}
+    get_mode: async: false future_output: {"TypeName":{"generics":[],"ident":"Result_OkMode_ErrStr"}} ok: {"TypeName":{"generics":[],"ident":"Mode"}} call: null
+    receipt: async: false future_output: null ok: null call: null
+    subscribe: async: true future_output: null ok: null call: {"ServerStreaming":{"item":"U32"}}
+    upload: async: false future_output: "U64" ok: null call: {"ClientStreaming":{"item":{"Seq":"U8"},"param":"chunks"}}
+    watch_modes: async: false future_output: null ok: null call: {"ServerStreaming":{"item":{"TypeName":{"generics":[],"ident":"Mode"}}}}
+    "###);
+}
+
+/// Stand-ins for web framework extractors
+mod extract {
+    pub struct Json<T>(pub T);
+    pub struct Path<T>(pub T);
+    pub struct Query<T>(pub T);
+    pub struct State<T>(pub T);
+    pub struct Cbor<T>(pub T);
+}
+
+#[derive(Codegen)]
+#[codegen(tags = "fn-extractors")]
+struct CreateUser {
+    name: String,
+}
+
+#[i_codegen_derive::fn_codegen(
+    tags = "fn-extractors",
+    extractors = "axum",
+    extractor = "Cbor = body"
+)]
+fn create_user(
+    extract::State(store): extract::State<UserStore>,
+    extract::Path((org, team)): extract::Path<(u64, u64)>,
+    extract::Query(mode): extract::Query<Mode>,
+    extract::Json(body): extract::Json<CreateUser>,
+    extract::Cbor(raw): extract::Cbor<Vec<u8>>,
+    path: &std::path::Path,
+) {
+}
+
+/// Named like actix-web's `Data`, which is only an extractor with `extractors = "actix"`
+#[derive(Codegen)]
+#[codegen(tags = "fn-extractors")]
+struct Data<T> {
+    value: T,
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-extractors")]
+fn save(data: Data<u32>) {}
+
+#[test]
+fn test_fn_extractors() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("fn-extractors").to_input_json()).unwrap();
+    let mut functions = input["functions"].as_array().unwrap().clone();
+    functions.sort_by_key(|function| function["id"].as_str().unwrap().to_string());
+    let params = functions
+        .iter()
+        .flat_map(|function| {
+            let function_id = function["id"].as_str().unwrap();
+            function["function"]["params"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(move |param| (function_id, param))
+        })
+        .map(|(function_id, param)| {
+            format!(
+                "{function_id}.{}: {} from {} {}",
+                param["id"].as_str().unwrap(),
+                param["format"],
+                param["extractor"]["ident"],
+                param["extractor"]["source"]
+            )
+        })
+        .collect::<Vec<_>>();
+    insta::assert_snapshot!(params.join("\n"), @r###"
+    create_user.store: {"TypeName":{"generics":[],"ident":"UserStore"}} from "State" "State"
+    create_user.arg1: {"Tuple":["U64","U64"]} from "Path" "Path"
+    create_user.mode: {"TypeName":{"generics":[],"ident":"Mode"}} from "Query" "Query"
+    create_user.body: {"TypeName":{"generics":[],"ident":"CreateUser"}} from "Json" "Body"
+    create_user.raw: {"Seq":"U8"} from "Cbor" "Body"
+    create_user.path: "Str" from null null
+    save.data: {"TypeName":{"generics":["U32"],"ident":"Data"}} from null null
+    "###);
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-routes", method = "get", path = "/users/{id}")]
+fn get_user(id: u64, verbose: bool) -> Result<CreateUser, Mode> {
+    Err(Mode::Fast)
+}
+
+#[i_codegen_derive::fn_codegen(
+    tags = "fn-routes",
+    extractors = "axum",
+    method = "PUT",
+    path = "/orgs/:org/teams/:team"
+)]
+async fn update_team(
+    extract::State(store): extract::State<UserStore>,
+    extract::Path((org, team)): extract::Path<(u64, String)>,
+    extract::Json(body): extract::Json<CreateUser>,
+) -> u32 {
+    0
+}
+
+/// Stands in for actix-web, whose route attributes like `#[actix_web::get("/health/{service}")]`
+/// are only seen by `#[fn_codegen]` when they're below it. `#[codegen]` leaves functions as they are.
+mod actix_web {
+    pub use i_codegen_derive::codegen as get;
+}
+
+/// Named like an HTTP method, but not a framework's route
+mod cache {
+    pub use i_codegen_derive::codegen as get;
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-routes")]
+#[actix_web::get("/health/{service}")]
+fn health(service: String) -> bool {
+    true
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-routes")]
+#[cache::get("/cached/{key}")]
+fn cached(key: String) -> bool {
+    true
+}
+
+#[test]
+fn test_fn_routes() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("fn-routes").to_input_json()).unwrap();
+    let mut operations = input["operations"].as_array().unwrap().clone();
+    operations.sort_by_key(|operation| operation["function_id"].as_str().unwrap().to_string());
+    insta::assert_snapshot!(serde_json::to_string_pretty(&operations).unwrap(), @r###"
+    [
+      {
+        "body_params": [],
+        "error": {
+          "TypeName": {
+            "generics": [],
+            "ident": "Mode"
+          }
+        },
+        "function_id": "get_user",
+        "header_params": [],
+        "method": "GET",
+        "path": "/users/{id}",
+        "path_location": "L(derive-codegen/src/test.rs:2428 #B74248-B74261)",
+        "path_params": [
+          {
+            "format": "U64",
+            "name": "id",
+            "param": "id",
+            "param_index": 0
+          }
+        ],
+        "query_params": [
+          {
+            "format": "Bool",
+            "name": "verbose",
+            "param": "verbose",
+            "param_index": 1
+          }
+        ],
+        "success": {
+          "TypeName": {
+            "generics": [],
+            "ident": "CreateUser"
+          }
+        }
+      },
+      {
+        "body_params": [],
+        "error": null,
+        "function_id": "health",
+        "header_params": [],
+        "method": "GET",
+        "path": "/health/{service}",
+        "path_location": "L(derive-codegen/src/test.rs:2458 #B75158-B75177)",
+        "path_params": [
+          {
+            "format": "Str",
+            "name": "service",
+            "param": "service",
+            "param_index": 0
+          }
+        ],
+        "query_params": [],
+        "success": "Bool"
+      },
+      {
+        "body_params": [
+          {
+            "format": {
+              "TypeName": {
+                "generics": [],
+                "ident": "CreateUser"
+              }
+            },
+            "name": "body",
+            "param": "body",
+            "param_index": 2
+          }
+        ],
+        "error": null,
+        "function_id": "update_team",
+        "header_params": [],
+        "method": "PUT",
+        "path": "/orgs/{org}/teams/{team}",
+        "path_location": "L(derive-codegen/src/test.rs:2433 #B74465-B74489)",
+        "path_params": [
+          {
+            "field": 0,
+            "format": "U64",
+            "name": "org",
+            "param": "org",
+            "param_index": 1
+          },
+          {
+            "field": 1,
+            "format": "Str",
+            "name": "team",
+            "param": "team",
+            "param_index": 1
+          }
+        ],
+        "query_params": [],
+        "success": "U32"
+      }
+    ]
+    "###);
+    // `#[cache::get]` isn't a framework's route
+    assert!(input["functions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|function| function["id"] == "cached"));
+    assert!(!operations
+        .iter()
+        .any(|operation| operation["function_id"] == "cached"));
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-dispatch", dispatch)]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-dispatch", dispatch)]
+fn greet(name: &str, excited: Option<bool>) -> String {
+    format!(
+        "Hello, {name}{}",
+        if excited == Some(true) { "!" } else { "." }
+    )
+}
+
+#[i_codegen_derive::fn_codegen(tags = "fn-dispatch", dispatch)]
+async fn lookup_mode(id: u32) -> Result<Mode, String> {
+    match id {
+        0 => Ok(Mode::Fast),
+        _ => Err(format!("no mode {id}")),
+    }
+}
+
+/// Polls a dispatched call, which never waits on anything in these tests
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+    match future.as_mut().poll(&mut cx) {
+        std::task::Poll::Ready(output) => output,
+        std::task::Poll::Pending => panic!("dispatched call did not complete"),
+    }
+}
+
+#[test]
+fn test_fn_dispatch() {
+    let dispatcher = derive_codegen::Dispatcher::for_tags(&["fn-dispatch"]);
+    let call = |name: &str, args: serde_json::Value| match block_on(dispatcher.call(name, args)) {
+        Ok(value) => value.to_string(),
+        Err(err) => format!("error: {err}"),
+    };
+    let calls = [
+        call("add", serde_json::json!({ "a": 1, "b": 2 })),
+        call("add", serde_json::json!([3, 4])),
+        call("add", serde_json::json!({ "a": 1, "c": 2 })),
+        call("add", serde_json::json!({ "a": "one", "b": 2 })),
+        call("greet", serde_json::json!({ "name": "Ada" })),
+        call("greet", serde_json::json!(["Ada", true])),
+        call("lookup_mode", serde_json::json!({ "id": 0 })),
+        call("lookup_mode", serde_json::json!({ "id": 7 })),
+        call("missing", serde_json::json!(null)),
+    ];
+    insta::assert_snapshot!(calls.join("\n"), @r###"
+    3
+    7
+    error: invalid arguments: unknown argument `c`
+    error: invalid arguments: argument `a`: invalid type: string "one", expected i32
+    "Hello, Ada."
+    "Hello, Ada!"
+    "Fast"
+    error: function failed: "no mode 7"
+    error: no function named `missing` to dispatch
+    "###);
+    assert_eq!(
+        dispatcher.names().collect::<Vec<_>>(),
+        ["add", "greet", "lookup_mode"]
+    );
+}
+
+/// The same function name in two modules, which must be dispatched with their module paths
+mod dispatch_v1 {
+    #[i_codegen_derive::fn_codegen(tags = "dispatch-modules", dispatch)]
+    pub fn version() -> u32 {
+        1
+    }
+}
+
+mod dispatch_v2 {
+    #[i_codegen_derive::fn_codegen(tags = "dispatch-modules", dispatch)]
+    pub fn version() -> u32 {
+        2
+    }
+}
+
+#[test]
+fn test_fn_dispatch_modules() {
+    let dispatcher = derive_codegen::Dispatcher::for_tags(&["dispatch-modules"]);
+    let call = |name: &str| match block_on(dispatcher.call(name, serde_json::Value::Null)) {
+        Ok(value) => value.to_string(),
+        Err(err) => format!("error: {err}"),
+    };
+    let calls = [
+        call("version"),
+        call("derive_codegen::test::dispatch_v1::version"),
+        call("derive_codegen::test::dispatch_v2::version"),
+        call("derive_codegen::test::dispatch_v3::version"),
+    ];
+    insta::assert_snapshot!(calls.join("\n"), @r###"
+    error: more than one function named `version` to dispatch, call one of `derive_codegen::test::dispatch_v1::version`, `derive_codegen::test::dispatch_v2::version`
+    1
+    2
+    error: no function named `derive_codegen::test::dispatch_v3::version` to dispatch
+    "###);
+    insta::assert_snapshot!(dispatcher.names().collect::<Vec<_>>().join("\n"), @r###"
+    derive_codegen::test::dispatch_v1::version
+    derive_codegen::test::dispatch_v2::version
+    "###);
+}
+
+/// Serves dispatched calls from JSON strings like a transport would, such as a WebSocket,
+/// on another thread with requests and responses over channels
+#[test]
+fn test_fn_dispatch_transport() {
+    let (request_tx, request_rx) = std::sync::mpsc::channel::<String>();
+    let (response_tx, response_rx) = std::sync::mpsc::channel::<String>();
+    let server = std::thread::spawn(move || {
+        let dispatcher = derive_codegen::Dispatcher::for_tags(&["fn-dispatch"]);
+        for request in request_rx {
+            let request: serde_json::Value = serde_json::from_str(&request).unwrap();
+            let function = request["function"].as_str().unwrap();
+            let response = match block_on(dispatcher.call(function, request["args"].clone())) {
+                Ok(value) => serde_json::json!({ "id": request["id"], "ok": value }),
+                Err(derive_codegen::DispatchError::Failed(err)) => {
+                    serde_json::json!({ "id": request["id"], "err": err })
+                }
+                Err(err) => serde_json::json!({ "id": request["id"], "error": err.to_string() }),
+            };
+            response_tx.send(response.to_string()).unwrap();
+        }
+    });
+
+    let requests = [
+        serde_json::json!({ "id": 1, "function": "add", "args": [1, 2] }),
+        serde_json::json!({ "id": 2, "function": "greet", "args": { "name": "Ada", "excited": true } }),
+        serde_json::json!({ "id": 3, "function": "lookup_mode", "args": { "id": 0 } }),
+        serde_json::json!({ "id": 4, "function": "lookup_mode", "args": { "id": 7 } }),
+        serde_json::json!({ "id": 5, "function": "missing", "args": null }),
+    ];
+    for request in requests {
+        request_tx.send(request.to_string()).unwrap();
+    }
+    drop(request_tx);
+    server.join().unwrap();
+    let responses = response_rx.iter().collect::<Vec<_>>();
+    insta::assert_snapshot!(responses.join("\n"), @r###"
+    {"id":1,"ok":3}
+    {"id":2,"ok":"Hello, Ada!"}
+    {"id":3,"ok":"Fast"}
+    {"err":"no mode 7","id":4}
+    {"error":"no function named `missing` to dispatch","id":5}
+    "###);
+}
+
+#[i_codegen_derive::codegen(tags = "mod-defaults", package = "api")]
+mod api_defaults {
+    use super::*;
+
+    #[derive(Codegen, Serialize)]
+    pub struct Account {
+        id: u32,
+    }
+
+    #[derive(Codegen, Serialize)]
+    #[codegen(package = "accounts")]
+    pub struct AccountOverride {
+        id: u32,
+    }
+
+    #[i_codegen_derive::fn_codegen(internal)]
+    pub fn list_accounts() -> Vec<Account> {
+        Vec::new()
+    }
+
+    #[i_codegen_derive::const_codegen(tag = "mod-defaults-other")]
+    pub const MAX_ACCOUNTS: usize = 10;
+
+    #[i_codegen_derive::codegen(package = "nested")]
+    pub mod nested {
+        use super::*;
+
+        #[i_codegen_derive::codegen]
+        pub type AccountId = u32;
+    }
+}
+
+/// e.g. `package = "api", hidden` for the `codegen_attrs` and `codegen_flags` of an item
+fn codegen_attr_summary(item: &serde_json::Value) -> String {
+    let mut attrs = item["codegen_attrs"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| format!("{key} = {}", value[0]))
+        .collect::<Vec<_>>();
+    attrs.extend(
+        item["codegen_flags"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(flag, _)| flag.clone()),
+    );
+    attrs.join(", ")
+}
+
+/// One line for each item, sorted by id, with its [codegen_attr_summary]
+fn codegen_attr_lines(input: &serde_json::Value) -> String {
+    let mut lines = Vec::new();
+    for (section, items) in input.as_object().unwrap() {
+        for item in items.as_array().unwrap() {
+            lines.push(format!(
+                "{section} {}: {}",
+                item["id"].as_str().unwrap(),
+                codegen_attr_summary(item)
+            ));
+        }
+    }
+    lines.sort();
+    lines.join("\n")
+}
+
+#[test]
+fn test_mod_defaults() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("mod-defaults").to_input_json()).unwrap();
+    insta::assert_snapshot!(codegen_attr_lines(&input), @r###"
+    aliases AccountId: package = "nested", tags = "mod-defaults"
+    declarations Account: package = "api", tags = "mod-defaults"
+    declarations AccountOverride: package = "accounts", tags = "mod-defaults"
+    functions list_accounts: package = "api", tags = "mod-defaults", internal
+    "###);
+    let other: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("mod-defaults-other").to_input_json()).unwrap();
+    insta::assert_snapshot!(codegen_attr_lines(&other), @r###"
+    constants MAX_ACCOUNTS: package = "api", tag = "mod-defaults-other"
+    "###);
+}
+
+#[i_codegen_derive::codegen(tags = "mod-scoped-defaults", ts(as = "string", optional))]
+mod scoped_defaults {
+    use super::*;
+
+    #[derive(Codegen, Serialize)]
+    pub struct Invoice {
+        id: u32,
+    }
+
+    #[derive(Codegen, Serialize)]
+    #[codegen(ts(as = "number"))]
+    pub struct InvoiceNumber(u32);
+
+    #[derive(Codegen, Serialize)]
+    #[codegen(ts_as = "bigint")]
+    pub struct InvoiceTotal(u64);
+
+    #[i_codegen_derive::codegen(ts(as = "Date"))]
+    pub mod dates {
+        use super::*;
+
+        #[derive(Codegen, Serialize)]
+        pub struct InvoiceDate(String);
+
+        #[derive(Codegen, Serialize)]
+        #[codegen(ts(optional = "false"))]
+        pub struct InvoiceDueDate(String);
+    }
+}
+
+#[test]
+fn test_mod_scoped_defaults() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("mod-scoped-defaults").to_input_json()).unwrap();
+    insta::assert_snapshot!(codegen_attr_lines(&input), @r###"
+    declarations Invoice: tags = "mod-scoped-defaults", ts_as = "string", ts_optional
+    declarations InvoiceDate: tags = "mod-scoped-defaults", ts_as = "Date", ts_optional
+    declarations InvoiceDueDate: tags = "mod-scoped-defaults", ts_as = "Date", ts_optional = "false"
+    declarations InvoiceNumber: tags = "mod-scoped-defaults", ts_as = "number", ts_optional
+    declarations InvoiceTotal: tags = "mod-scoped-defaults", ts_as = "bigint", ts_optional
+    "###);
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "member-tags, member-tags-admin")]
+struct Profile {
+    name: String,
+    #[codegen(tags = "member-tags-admin")]
+    email: Option<String>,
+    #[codegen(tags = "member-tags-admin")]
+    #[serde(default)]
+    logins: u32,
+    #[codegen(tags = "member-tags-admin")]
+    password_hash: String,
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "member-tags, member-tags-admin")]
+enum ProfileEvent {
+    Renamed {
+        name: String,
+        #[codegen(tags = "member-tags-admin")]
+        previous: Option<String>,
+    },
+    #[codegen(tags = "member-tags-admin")]
+    Banned,
+}
+
+/// One line for each field or variant of the declarations, e.g. `Profile.name`
+fn member_lines(input: &serde_json::Value) -> String {
+    let mut lines = Vec::new();
+    for declaration in input["declarations"].as_array().unwrap() {
+        let id = declaration["id"].as_str().unwrap();
+        let container = &declaration["container_kind"];
+        let members = container["Struct"]["fields"]
+            .as_array()
+            .or_else(|| container["Enum"]["variants"].as_array())
+            .unwrap();
+        for member in members {
+            lines.push(format!("{id}.{}", member["id"].as_str().unwrap()));
+            let fields = member["variant_format"]["Struct"]["fields"].as_array();
+            for field in fields.into_iter().flatten() {
+                lines.push(format!(
+                    "{id}.{}.{}",
+                    member["id"].as_str().unwrap(),
+                    field["id"].as_str().unwrap()
+                ));
+            }
+        }
+    }
+    lines.sort();
+    lines.join("\n")
+}
+
+#[test]
+fn test_member_tags() {
+    let public: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("member-tags").to_input_json()).unwrap();
+    insta::assert_snapshot!(member_lines(&public), @r###"
+    Profile.name
+    ProfileEvent.Renamed
+    ProfileEvent.Renamed.name
+    "###);
+    let warnings = public["warnings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| warning.as_str().unwrap())
+        .collect::<Vec<_>>();
+    insta::assert_snapshot!(warnings.join("\n"), @r###"
+    `Profile.password_hash` is excluded by its tags ["member-tags-admin"], but is required to deserialize `Profile` as it is neither an `Option` nor `#[serde(default)]` (derive-codegen/src/test.rs:2861)
+    "###);
+    let admin: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("member-tags-admin").to_input_json()).unwrap();
+    insta::assert_snapshot!(member_lines(&admin), @r###"
+    Profile.email
+    Profile.logins
+    Profile.name
+    Profile.password_hash
+    ProfileEvent.Banned
+    ProfileEvent.Renamed
+    ProfileEvent.Renamed.name
+    ProfileEvent.Renamed.previous
+    "###);
+    assert!(admin.get("warnings").is_none());
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "scoped-attrs, scoped-attrs-admin")]
+struct ScopedAttrs {
+    #[codegen(ts(as = "Date"), go(type = "time.Time"))]
+    created: u64,
+    #[codegen(when(tag = "scoped-attrs-admin", rename = "internal_note", hidden))]
+    #[codegen(go(when(tag = "scoped-attrs-admin", skip)))]
+    note: String,
+}
+
+/// The [codegen_attr_summary] of each field of `ScopedAttrs` in the generation
+fn scoped_attr_lines(generation: &Generation) -> String {
+    let input: serde_json::Value = serde_json::from_str(&generation.to_input_json()).unwrap();
+    input["declarations"][0]["container_kind"]["Struct"]["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| {
+            format!(
+                "{}: {}",
+                field["id"].as_str().unwrap(),
+                codegen_attr_summary(field)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn test_scoped_attrs() {
+    insta::assert_snapshot!(scoped_attr_lines(&Generation::for_tag("scoped-attrs")), @r###"
+    created: go_type = "time.Time", ts_as = "Date"
+    note: 
+    "###);
+    insta::assert_snapshot!(scoped_attr_lines(&Generation::for_tag("scoped-attrs-admin")), @r###"
+    created: go_type = "time.Time", ts_as = "Date"
+    note: rename = "internal_note", go_skip, hidden
+    "###);
+    let mut go_admin = Generation::for_tag("scoped-attrs-admin");
+    go_admin.include_target("go");
+    insta::assert_snapshot!(scoped_attr_lines(&go_admin), @r###"
+    created: type = "time.Time"
+    note: rename = "internal_note", hidden, skip
+    "###);
+}
+
+/// Without tags, so only selected by `*` among tag expressions
+#[derive(Codegen, Serialize)]
+struct Untagged {
+    id: u32,
+}
+
+/// e.g. `declarations Untagged` for each item of the generation, sorted
+fn selected_ids(generation: &Generation) -> String {
+    let input: serde_json::Value = serde_json::from_str(&generation.to_input_json()).unwrap();
+    let mut lines = Vec::new();
+    for (section, items) in input.as_object().unwrap() {
+        if section == "warnings" {
+            continue;
+        }
+        for item in items.as_array().unwrap() {
+            // operations are identified by their function
+            let id = item.get("id").unwrap_or(&item["function_id"]);
+            let dependency = if item["included_as_dependency"] == true {
+                " (dependency)"
+            } else {
+                ""
+            };
+            lines.push(format!("{section} {}{dependency}", id.as_str().unwrap()));
+        }
+    }
+    lines.sort();
+    lines.join("\n")
+}
+
+#[test]
+fn test_tag_expressions() {
+    insta::assert_snapshot!(selected_ids(&Generation::for_tag("fn-args | aliases")), @r###"
+    aliases Page
+    aliases UserId
+    declarations Paginated
+    functions tagged_by_args
+    functions tagged_by_args_and_attrs
+    "###);
+    insta::assert_snapshot!(selected_ids(&Generation::for_tag(
+        "fn-* & !(fn-args | fn-dispatch | fn-extractors | fn-results | fn-routes | fn-streams)"
+    )), @r###"functions first_matching"###);
+    let everything = selected_ids(&Generation::for_tag("*"));
+    assert!(everything.contains("declarations Untagged"));
+    assert!(everything.contains("declarations UnitType"));
+    assert!(!selected_ids(&Generation::for_tag("!fsharp")).contains("declarations UnitType"));
+
+    let errors = [
+        "",
+        "api &",
+        "(api | admin",
+        "api admin",
+        "api && admin",
+        "!)",
+    ]
+    .map(|expr| {
+        derive_codegen::TagExpr::parse(expr)
+            .unwrap_err()
+            .to_string()
+    })
+    .join("\n");
+    insta::assert_snapshot!(errors, @r###"
+    invalid tag expression ``: expected a tag at offset 0
+    invalid tag expression `api &`: expected a tag at offset 5
+    invalid tag expression `(api | admin`: expected `)` at offset 12
+    invalid tag expression `api admin`: expected `&`, `|` or the end of the expression at offset 4
+    invalid tag expression `api && admin`: expected a tag at offset 5
+    invalid tag expression `!)`: expected a tag at offset 1
+    "###);
+}
+
+#[test]
+fn test_item_filters() {
+    let mut api_defaults = Generation::default();
+    api_defaults.include_module("derive_codegen::test::api_defaults");
+    insta::assert_snapshot!(selected_ids(&api_defaults), @r###"
+    aliases AccountId
+    constants MAX_ACCOUNTS
+    declarations Account
+    declarations AccountOverride
+    functions list_accounts
+    "###);
+    api_defaults
+        .exclude_module("derive_codegen::test::api_defaults::nested")
+        .exclude_ids(["MAX_ACCOUNTS"]);
+    insta::assert_snapshot!(selected_ids(&api_defaults), @r###"
+    declarations Account
+    declarations AccountOverride
+    functions list_accounts
+    "###);
+
+    let mut with_ids = Generation::for_tag("fn-args");
+    // `serde_attrs::Untagged` is left out by the path, and `Mode` isn't declared at the crate root
+    with_ids.include_ids(["derive_codegen::test::Untagged", "derive_codegen::Mode"]);
+    insta::assert_snapshot!(selected_ids(&with_ids), @r###"
+    declarations Untagged
+    functions tagged_by_args
+    functions tagged_by_args_and_attrs
+    "###);
+
+    let mut other_crates = Generation::for_tag("*");
+    other_crates.exclude_crate("derive-codegen");
+    insta::assert_snapshot!(selected_ids(&other_crates), @"");
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "deps")]
+struct Order {
+    total: Money,
+    lines: Vec<OrderLine>,
+    notes: Option<NotRegistered>,
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "deps")]
+struct OrderLine {
+    price: Money,
+}
+
+#[derive(Codegen, Serialize)]
+struct Money {
+    amount: i64,
+    currency: Currency,
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "deps-other")]
+enum Currency {
+    Eur,
+    Usd,
+}
+
+/// Without `Codegen`, so never included
+#[derive(Serialize)]
+struct NotRegistered;
+
+/// Not selected itself, only its `impl` block is
+#[derive(Codegen, Serialize)]
+struct ProbeStore {
+    probes: u32,
+}
+
+#[i_codegen_derive::codegen(tags = "deps-service")]
+impl ProbeStore {
+    pub fn new() -> Self {
+        ProbeStore { probes: 0 }
+    }
+    pub fn probe(&self) -> u32 {
+        self.probes
+    }
+}
+
+#[test]
+fn test_dependencies() {
+    insta::assert_snapshot!(selected_ids(&Generation::for_tag("deps")), @r###"
+    declarations Currency (dependency)
+    declarations Money (dependency)
+    declarations Order
+    declarations OrderLine
+    "###);
+    let mut without_currency = Generation::for_tag("deps");
+    without_currency.exclude_ids(["Currency"]);
+    insta::assert_snapshot!(selected_ids(&without_currency), @r###"
+    declarations Money (dependency)
+    declarations Order
+    declarations OrderLine
+    "###);
+
+    let mut strict = Generation::for_tag("deps");
+    strict.strict_dependencies();
+    insta::assert_snapshot!(strict.try_to_input_json().unwrap_err().to_string(), @r###"
+    types referenced by the generation aren't selected:
+     * `Money` referenced by `Order` at L(derive-codegen/src/test.rs:3095 #B96484-B96489)
+     * `NotRegistered` referenced by `Order` at L(derive-codegen/src/test.rs:3095 #B96529-B96534)
+     * `Money` referenced by `OrderLine` at L(derive-codegen/src/test.rs:3103 #B96641-B96646)
+    "###);
+
+    insta::assert_snapshot!(selected_ids(&Generation::for_tag("deps-service")), @r###"
+    declarations ProbeStore (dependency)
+    services ProbeStoreImpl
+    "###);
+    let mut strict_service = Generation::for_tag("deps-service");
+    strict_service.strict_dependencies();
+    insta::assert_snapshot!(strict_service.try_to_input_json().unwrap_err().to_string(), @r###"
+    types referenced by the generation aren't selected:
+     * `ProbeStore` referenced by `ProbeStoreImpl` at L(derive-codegen/src/test.rs:3132 #B97132-B97135)
+    "###);
+}
+
+#[test]
+fn test_generate() {
+    insta::assert_snapshot!(Generation::for_tag("docs").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "Basically",
+          "id_location": "L(derive-codegen/src/test.rs:84 #B2582-B2591)",
+          "rust_docs": "Test doc comment\nSecond line\n```sh\nSome code\n```\nReference to [BasicEnum].",
+          "rust_docs_parsed": {
+            "summary": "Test doc comment\nSecond line",
+            "body": "```sh\nSome code\n```\nReference to [BasicEnum].",
+            "code_blocks": [
+              {
+                "lang": "sh",
+                "code": "Some code"
+              }
+            ],
+            "links": [
+              {
+                "text": "BasicEnum",
+                "target": "BasicEnum",
+                "id": null,
+                "member": null
+              }
+            ]
+          },
+          "serde_attrs": {
+            "rename": [
+              "basically",
+              "L(derive-codegen/src/test.rs:84 #B2535-B2546)"
+            ]
+          },
+          "serde_attrs_repeated": {
+            "rename": [
+              [
+                "basically",
+                "L(derive-codegen/src/test.rs:84 #B2535-B2546)"
+              ]
+            ]
+          },
+          "serde_meta": [
+            {
+              "key": "rename",
+              "key_location": "L(derive-codegen/src/test.rs:84 #B2526-B2532)",
+              "value": {
+                "Lit": {
+                  "value": "basically",
+                  "location": "L(derive-codegen/src/test.rs:84 #B2535-B2546)"
+                }
+              }
+            }
+          ],
+          "codegen_attrs": {
+            "tags": [
+              "docs",
+              "L(derive-codegen/src/test.rs:84 #B2566-B2572)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "docs",
+                "L(derive-codegen/src/test.rs:84 #B2566-B2572)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:84 #B2582-B2591)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "a",
+                  "id_location": "L(derive-codegen/src/test.rs:84 #B2618-B2619)",
+                  "rust_docs": "Doc comment",
+                  "rust_docs_parsed": {
+                    "summary": "Doc comment"
+                  },
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:84 #B2618-B2619)"
+                  ],
+                  "format": "USIZE"
+                },
+                {
+                  "id": "b",
+                  "id_location": "L(derive-codegen/src/test.rs:84 #B2632-B2633)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:84 #B2632-B2633)"
+                  ],
+                  "format": "Str"
+                }
+              ]
+            }
+          }
+        },
+        {
+          "id": "ActionResult",
+          "id_location": "L(derive-codegen/src/test.rs:103 #B2926-B2938)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "docs",
+              "L(derive-codegen/src/test.rs:103 #B2910-B2916)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "docs",
+                "L(derive-codegen/src/test.rs:103 #B2910-B2916)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:103 #B2926-B2938)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "result",
+                  "id_location": "L(derive-codegen/src/test.rs:103 #B2945-B2951)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:103 #B2945-B2951)"
+                  ],
+                  "format": {
+                    "TypeName": {
+                      "ident": "Result_OkBasicallyOther_ErrStr",
+                      "generics": []
+                    }
+                  }
+                }
+              ]
+            }
+          }
+        },
+        {
+          "id": "Result_OkBasicallyOther_ErrStr",
+          "id_location": "L(derive-codegen/src/test.rs:103 #B2953-B2959)",
+          "rust_docs": "`Result` is a type that represents either success ([`Ok`]) or failure ([`Err`]).",
+          "rust_docs_parsed": {
+            "summary": "`Result` is a type that represents either success ([`Ok`]) or failure ([`Err`]).",
+            "links": [
+              {
+                "text": "`Ok`",
+                "target": "`Ok`",
+                "id": null,
+                "member": null
+              },
+              {
+                "text": "`Err`",
+                "target": "`Err`",
+                "id": null,
+                "member": null
+              }
+            ]
+          },
+          "container_kind": {
+            "Enum": {
+              "repr": "External",
+              "variants": [
+                {
+                  "id": "Ok",
+                  "id_location": "L(derive-codegen/src/test.rs:103 #B2953-B2959)",
+                  "index": 0,
+                  "rust_docs": "Contains the success value",
+                  "rust_docs_parsed": {
+                    "summary": "Contains the success value"
+                  },
+                  "variant_format": {
+                    "NewType": {
+                      "TypeName": {
+                        "ident": "BasicallyOther",
+                        "generics": []
+                      }
+                    }
+                  }
+                },
+                {
+                  "id": "Err",
+                  "id_location": "L(derive-codegen/src/test.rs:103 #B2953-B2959)",
+                  "index": 1,
+                  "rust_docs": "Contains the error value",
+                  "rust_docs_parsed": {
+                    "summary": "Contains the error value"
+                  },
+                  "variant_format": {
+                    "NewType": "Str"
+                  }
+                }
+              ]
+            }
+          }
+        },
+        {
+          "id": "BasicallyOther",
+          "id_location": "L(derive-codegen/src/test.rs:93 #B2711-B2725)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "docs",
+              "L(derive-codegen/src/test.rs:93 #B2695-B2701)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "docs",
+                "L(derive-codegen/src/test.rs:93 #B2695-B2701)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:93 #B2711-B2725)"
+          ],
+          "container_kind": {
+            "Struct": {
+              "fields": [
+                {
+                  "id": "usize_opt",
+                  "id_location": "L(derive-codegen/src/test.rs:93 #B2817-B2826)",
+                  "rust_docs": "Other option",
+                  "rust_docs_parsed": {
+                    "summary": "Other option"
+                  },
+                  "serde_attrs": {
+                    "alias": [
+                      "usize",
+                      "L(derive-codegen/src/test.rs:93 #B2803-B2810)"
+                    ],
+                    "rename": [
+                      "usizeOpt",
+                      "L(derive-codegen/src/test.rs:93 #B2770-B2780)"
+                    ]
+                  },
+                  "serde_attrs_repeated": {
+                    "alias": [
+                      [
+                        "usize",
+                        "L(derive-codegen/src/test.rs:93 #B2803-B2810)"
+                      ]
+                    ],
+                    "rename": [
+                      [
+                        "usizeOpt",
+                        "L(derive-codegen/src/test.rs:93 #B2770-B2780)"
+                      ]
+                    ]
+                  },
+                  "serde_meta": [
+                    {
+                      "key": "rename",
+                      "key_location": "L(derive-codegen/src/test.rs:93 #B2761-B2767)",
+                      "value": {
+                        "Lit": {
+                          "value": "usizeOpt",
+                          "location": "L(derive-codegen/src/test.rs:93 #B2770-B2780)"
+                        }
+                      }
+                    },
+                    {
+                      "key": "alias",
+                      "key_location": "L(derive-codegen/src/test.rs:93 #B2795-B2800)",
+                      "value": {
+                        "Lit": {
+                          "value": "usize",
+                          "location": "L(derive-codegen/src/test.rs:93 #B2803-B2810)"
+                        }
+                      }
+                    }
+                  ],
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:93 #B2817-B2826)"
+                  ],
+                  "format": {
+                    "Option": "USIZE"
+                  }
+                },
+                {
+                  "id": "b",
+                  "id_location": "L(derive-codegen/src/test.rs:93 #B2847-B2848)",
+                  "rust_docs": null,
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:93 #B2847-B2848)"
+                  ],
+                  "format": "Str"
+                }
+              ]
+            }
+          }
+        }
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+#[test]
+fn test_tuple_members() {
+    insta::assert_snapshot!(Generation::for_tag("tuple-members").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "ATupleVariant",
+          "id_location": "L(derive-codegen/src/test.rs:56 #B2045-B2058)",
+          "rust_docs": null,
+          "codegen_attrs": {
+            "tags": [
+              "tuple-members",
+              "L(derive-codegen/src/test.rs:56 #B2022-B2037)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "tuple-members",
+                "L(derive-codegen/src/test.rs:56 #B2022-B2037)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:56 #B2045-B2058)"
+          ],
+          "container_kind": {
+            "Enum": {
+              "repr": "External",
+              "variants": [
+                {
+                  "id": "Point",
+                  "id_location": "L(derive-codegen/src/test.rs:56 #B2065-B2070)",
+                  "index": 0,
+                  "discriminant": 0,
+                  "rust_docs": null,
+                  "variant_format": {
+                    "Tuple": {
+                      "fields": [
+                        {
+                          "id": "0",
+                          "id_location": "L(derive-codegen/src/test.rs:56 #B2112-B2117)",
+                          "rust_docs": "Horizontal position",
+                          "rust_docs_parsed": {
+                            "summary": "Horizontal position"
+                          },
+                          "rust_visibility": [
+                            "",
+                            "L(derive-codegen/src/test.rs:56 #B2112-B2117)"
+                          ],
+                          "format": "USIZE"
+                        },
+                        {
+                          "id": "1",
+                          "id_location": "L(derive-codegen/src/test.rs:56 #B2127-B2132)",
+                          "rust_docs": null,
+                          "rust_visibility": [
+                            "",
+                            "L(derive-codegen/src/test.rs:56 #B2127-B2132)"
+                          ],
+                          "format": "USIZE"
+                        }
+                      ]
+                    }
+                  }
+                }
+              ]
+            }
+          }
+        },
+        {
+          "id": "ATuplePartiallyNamed",
+          "id_location": "L(derive-codegen/src/test.rs:45 #B1812-B1832)",
+          "rust_docs": "These renames don't actually affect the generation.\nSee [Feature request: Allow `#[serde(rename = \"...\")]` on tuple struct / tuple variant member fields #1510](https://github.com/serde-rs/serde/issues/1510)",
+          "rust_docs_parsed": {
+            "summary": "These renames don't actually affect the generation.\nSee [Feature request: Allow `#[serde(rename = \"...\")]` on tuple struct / tuple variant member fields #1510](https://github.com/serde-rs/serde/issues/1510)",
+            "links": [
+              {
+                "text": "Feature request: Allow `#[serde(rename = \"...\")]` on tuple struct / tuple variant member fields #1510",
+                "target": "https://github.com/serde-rs/serde/issues/1510",
+                "id": null,
+                "member": null
+              }
+            ]
+          },
+          "codegen_attrs": {
+            "tags": [
+              "tuple-members",
+              "L(derive-codegen/src/test.rs:45 #B1787-B1802)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "tuple-members",
+                "L(derive-codegen/src/test.rs:45 #B1787-B1802)"
+              ]
+            ]
+          },
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test.rs:45 #B1812-B1832)"
+          ],
+          "container_kind": {
+            "TupleStruct": {
+              "fields": [
+                {
+                  "id": "0",
+                  "id_location": "L(derive-codegen/src/test.rs:45 #B1893-B1898)",
+                  "rust_docs": "Horizontal position",
+                  "rust_docs_parsed": {
+                    "summary": "Horizontal position"
+                  },
+                  "serde_attrs": {
+                    "rename": [
+                      "x",
+                      "L(derive-codegen/src/test.rs:45 #B1883-B1886)"
+                    ]
+                  },
+                  "serde_attrs_repeated": {
+                    "rename": [
+                      [
+                        "x",
+                        "L(derive-codegen/src/test.rs:45 #B1883-B1886)"
+                      ]
+                    ]
+                  },
+                  "serde_meta": [
+                    {
+                      "key": "rename",
+                      "key_location": "L(derive-codegen/src/test.rs:45 #B1874-B1880)",
+                      "value": {
+                        "Lit": {
+                          "value": "x",
+                          "location": "L(derive-codegen/src/test.rs:45 #B1883-B1886)"
+                        }
+                      }
+                    }
+                  ],
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:45 #B1893-B1898)"
+                  ],
+                  "format": "USIZE"
+                },
+                {
+                  "id": "1",
+                  "id_location": "L(derive-codegen/src/test.rs:45 #B1964-B1969)",
+                  "rust_docs": null,
+                  "serde_attrs": {
+                    "rename": [
+                      "y",
+                      "L(derive-codegen/src/test.rs:45 #B1921-B1924)"
+                    ]
+                  },
+                  "serde_attrs_repeated": {
+                    "rename": [
+                      [
+                        "y",
+                        "L(derive-codegen/src/test.rs:45 #B1921-B1924)"
+                      ]
+                    ]
+                  },
+                  "serde_meta": [
+                    {
+                      "key": "rename",
+                      "key_location": "L(derive-codegen/src/test.rs:45 #B1912-B1918)",
+                      "value": {
+                        "Lit": {
+                          "value": "y",
+                          "location": "L(derive-codegen/src/test.rs:45 #B1921-B1924)"
+                        }
+                      }
+                    }
+                  ],
+                  "codegen_attrs": {
+                    "ts_as": [
+                      "number",
+                      "L(derive-codegen/src/test.rs:45 #B1949-B1957)"
+                    ]
+                  },
+                  "codegen_attrs_repeated": {
+                    "ts_as": [
+                      [
+                        "number",
+                        "L(derive-codegen/src/test.rs:45 #B1949-B1957)"
+                      ]
+                    ]
+                  },
+                  "rust_visibility": [
+                    "",
+                    "L(derive-codegen/src/test.rs:45 #B1964-B1969)"
+                  ],
+                  "format": "USIZE"
+                }
               ]
             }
           }
         }
-      ]
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
     }
     "###);
 }
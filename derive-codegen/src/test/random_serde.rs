@@ -1,8 +1,13 @@
+use i_codegen_derive::CodegenInternal as Codegen;
 use serde::Serialize;
+use serde_repr::Serialize_repr;
+
+use crate::generate::Generation;
 
 /// Need to use serde_repr to use numbers
 /// https://serde.rs/enum-number.html
-#[derive(Serialize)]
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "enum-of-ints")]
 #[repr(u8)]
 enum EnumOfInts {
     A1 = 1,
@@ -10,6 +15,25 @@ enum EnumOfInts {
     C(usize),
 }
 
+/// `Serialize_repr` isn't visible to `Codegen` in the same `#[derive]`, so it's marked with `serde_repr`
+#[derive(Codegen, Serialize_repr)]
+#[codegen(tags = "enum-of-ints", serde_repr)]
+#[repr(u8)]
+enum EnumOfReprInts {
+    A1 = 1,
+    A2,
+    B = 1 << 4,
+}
+
+/// Serialized as numbers, but without `#[codegen(serde_repr)]`
+#[derive(Codegen, Serialize_repr)]
+#[codegen(tags = "enum-of-unmarked-ints")]
+#[repr(u16)]
+enum EnumOfUnmarkedReprInts {
+    A = 1,
+    B = 2,
+}
+
 /// Assert json inline with [`insta`]
 macro_rules! assert_json {
     ($t:expr, @$t2:expr) => {
@@ -23,3 +47,169 @@ fn test_enum_of_ints() {
     assert_json!(EnumOfInts::A2, @r###""A2""###);
     assert_json!(EnumOfInts::C(14), @r###"{"C":14}"###);
 }
+
+#[test]
+fn test_enum_of_repr_ints() {
+    assert_json!(EnumOfReprInts::A1, @r###"1"###);
+    assert_json!(EnumOfReprInts::A2, @r###"2"###);
+    assert_json!(EnumOfReprInts::B, @r###"16"###);
+    insta::assert_snapshot!(Generation::for_tag("enum-of-ints").to_input_json_pretty(), @r###"
+    {
+      "declarations": [
+        {
+          "id": "EnumOfInts",
+          "id_location": "L(derive-codegen/src/test/random_serde.rs:9 #B116664-B116674)",
+          "rust_docs": "Need to use serde_repr to use numbers\nhttps://serde.rs/enum-number.html",
+          "rust_docs_parsed": {
+            "summary": "Need to use serde_repr to use numbers\nhttps://serde.rs/enum-number.html"
+          },
+          "codegen_attrs": {
+            "tags": [
+              "enum-of-ints",
+              "L(derive-codegen/src/test/random_serde.rs:9 #B116630-B116644)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "enum-of-ints",
+                "L(derive-codegen/src/test/random_serde.rs:9 #B116630-B116644)"
+              ]
+            ]
+          },
+          "rust_repr": [
+            [
+              "u8",
+              "L(derive-codegen/src/test/random_serde.rs:9 #B116654-B116656)"
+            ]
+          ],
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test/random_serde.rs:9 #B116664-B116674)"
+          ],
+          "container_kind": {
+            "Enum": {
+              "repr": "External",
+              "variants": [
+                {
+                  "id": "A1",
+                  "id_location": "L(derive-codegen/src/test/random_serde.rs:9 #B116681-B116683)",
+                  "index": 0,
+                  "discriminant": 1,
+                  "rust_docs": null,
+                  "variant_format": "Unit"
+                },
+                {
+                  "id": "A2",
+                  "id_location": "L(derive-codegen/src/test/random_serde.rs:9 #B116693-B116695)",
+                  "index": 1,
+                  "discriminant": 2,
+                  "rust_docs": null,
+                  "variant_format": "Unit"
+                },
+                {
+                  "id": "C",
+                  "id_location": "L(derive-codegen/src/test/random_serde.rs:9 #B116705-B116706)",
+                  "index": 2,
+                  "discriminant": 3,
+                  "rust_docs": null,
+                  "variant_format": {
+                    "NewType": "USIZE"
+                  }
+                }
+              ]
+            }
+          }
+        },
+        {
+          "id": "EnumOfReprInts",
+          "id_location": "L(derive-codegen/src/test/random_serde.rs:19 #B116922-B116936)",
+          "rust_docs": "`Serialize_repr` isn't visible to `Codegen` in the same `#[derive]`, so it's marked with `serde_repr`",
+          "rust_docs_parsed": {
+            "summary": "`Serialize_repr` isn't visible to `Codegen` in the same `#[derive]`, so it's marked with `serde_repr`"
+          },
+          "codegen_attrs": {
+            "tags": [
+              "enum-of-ints",
+              "L(derive-codegen/src/test/random_serde.rs:19 #B116876-B116890)"
+            ]
+          },
+          "codegen_attrs_repeated": {
+            "tags": [
+              [
+                "enum-of-ints",
+                "L(derive-codegen/src/test/random_serde.rs:19 #B116876-B116890)"
+              ]
+            ]
+          },
+          "codegen_flags": {
+            "serde_repr": "L(derive-codegen/src/test/random_serde.rs:19 #B116902-B116903)"
+          },
+          "rust_repr": [
+            [
+              "u8",
+              "L(derive-codegen/src/test/random_serde.rs:19 #B116912-B116914)"
+            ]
+          ],
+          "rust_visibility": [
+            "",
+            "L(derive-codegen/src/test/random_serde.rs:19 #B116922-B116936)"
+          ],
+          "container_kind": {
+            "Enum": {
+              "repr": {
+                "Integer": {
+                  "repr": "u8"
+                }
+              },
+              "variants": [
+                {
+                  "id": "A1",
+                  "id_location": "L(derive-codegen/src/test/random_serde.rs:19 #B116943-B116945)",
+                  "index": 0,
+                  "discriminant": 1,
+                  "rust_docs": null,
+                  "variant_format": "Unit"
+                },
+                {
+                  "id": "A2",
+                  "id_location": "L(derive-codegen/src/test/random_serde.rs:19 #B116955-B116957)",
+                  "index": 1,
+                  "discriminant": 2,
+                  "rust_docs": null,
+                  "variant_format": "Unit"
+                },
+                {
+                  "id": "B",
+                  "id_location": "L(derive-codegen/src/test/random_serde.rs:19 #B116963-B116964)",
+                  "index": 2,
+                  "discriminant": 16,
+                  "rust_docs": null,
+                  "variant_format": "Unit"
+                }
+              ]
+            }
+          }
+        }
+      ],
+      "functions": [],
+      "constants": [],
+      "aliases": [],
+      "services": [],
+      "interfaces": [],
+      "operations": []
+    }
+    "###);
+}
+
+#[test]
+fn test_enum_of_unmarked_repr_ints() {
+    assert_json!(EnumOfUnmarkedReprInts::B, @r###"2"###);
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("enum-of-unmarked-ints").to_input_json())
+            .unwrap();
+    insta::assert_snapshot!(input["declarations"][0]["container_kind"]["Enum"]["repr"].to_string(), @r###"
+    "External"
+    "###);
+    insta::assert_snapshot!(input["warnings"][0].as_str().unwrap(), @r###"`EnumOfUnmarkedReprInts` has `#[repr(u16)]` but no serde derive is visible to `#[derive(Codegen)]`, so it's described by its variant names. Add `#[codegen(serde_repr)]` if it's serialized as numbers by `serde_repr` (derive-codegen/src/test/random_serde.rs:29)"###);
+}
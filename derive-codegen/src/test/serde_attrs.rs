@@ -0,0 +1,292 @@
+//! Every form of `#[serde(...)]` attribute which serde accepts, on containers, variants and fields.
+use i_codegen_derive::CodegenInternal as Codegen;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use crate::generate::Generation;
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(rename = "ContainerRenamed", rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+#[serde(expecting = "a container")]
+struct Container<T> {
+    #[serde(rename(serialize = "ser_name", deserialize = "de_name"))]
+    #[serde(alias = "a", alias = "b")]
+    renamed: T,
+    #[serde(default = "default_count", skip_serializing_if = "Option::is_none")]
+    count: Option<u32>,
+    #[serde(default)]
+    defaulted: u8,
+    #[serde(skip)]
+    skipped: u8,
+    #[serde(skip_serializing, skip_deserializing)]
+    skipped_both: u8,
+    #[serde(serialize_with = "ser_u8", deserialize_with = "de_u8")]
+    custom: u8,
+    #[serde(with = "u8_module")]
+    with_module: u8,
+    #[serde(bound = "T: Clone")]
+    bounded: Vec<T>,
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(rename_all(serialize = "SCREAMING_SNAKE_CASE", deserialize = "snake_case"))]
+#[serde(default = "Defaulted::new")]
+#[serde(crate = "serde")]
+struct Defaulted {
+    value: u8,
+    #[serde(flatten)]
+    extra: BTreeMap<String, String>,
+}
+
+impl Defaulted {
+    fn new() -> Self {
+        Defaulted {
+            value: 1,
+            extra: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
+enum Variants {
+    #[serde(rename(serialize = "first", deserialize = "one"), alias = "uno")]
+    First,
+    #[serde(rename_all = "UPPERCASE")]
+    Second { some_field: u8 },
+    #[serde(skip)]
+    Skipped,
+    #[serde(skip_serializing)]
+    NotSerialized,
+    #[serde(skip_deserializing)]
+    NotDeserialized,
+    #[serde(with = "u8_module")]
+    With(u8),
+    #[serde(serialize_with = "ser_u8", deserialize_with = "de_u8")]
+    Custom(u8),
+    #[serde(bound = "")]
+    Bounded(u8),
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(untagged)]
+enum Untagged {
+    Number(u8),
+    Text(String),
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(tag = "kind")]
+enum InternallyTagged {
+    Unit,
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(transparent)]
+struct Transparent(u8);
+
+#[derive(Codegen, Serialize, Deserialize, Clone)]
+#[codegen(tags = "serde-matrix")]
+#[serde(from = "u8", into = "u8")]
+struct Converted(u8);
+
+impl From<u8> for Converted {
+    fn from(value: u8) -> Self {
+        Converted(value)
+    }
+}
+
+impl From<Converted> for u8 {
+    fn from(value: Converted) -> Self {
+        value.0
+    }
+}
+
+#[derive(Codegen, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(try_from = "String")]
+struct TryConverted(String);
+
+impl TryFrom<String> for TryConverted {
+    type Error = &'static str;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(TryConverted(value))
+    }
+}
+
+#[derive(Codegen, Serialize, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+struct Borrowed<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(borrow = "'a")]
+    other: Cow<'a, str>,
+}
+
+#[derive(Codegen, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(variant_identifier)]
+enum VariantIdentifier {
+    A,
+    B,
+}
+
+#[derive(Codegen, Deserialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum FieldIdentifier {
+    A,
+    #[serde(other)]
+    Other,
+}
+
+mod remote {
+    pub struct Remote {
+        pub a: u8,
+        b: u8,
+    }
+
+    impl Remote {
+        pub fn b(&self) -> u8 {
+            self.b
+        }
+    }
+}
+
+#[derive(Codegen, Serialize)]
+#[codegen(tags = "serde-matrix")]
+#[serde(remote = "remote::Remote")]
+struct RemoteDef {
+    a: u8,
+    #[serde(getter = "remote::Remote::b")]
+    b: u8,
+}
+
+fn default_count() -> Option<u32> {
+    Some(1)
+}
+
+fn ser_u8<S: Serializer>(value: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u8(*value)
+}
+
+fn de_u8<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    u8::deserialize(deserializer)
+}
+
+mod u8_module {
+    pub(super) use super::{de_u8 as deserialize, ser_u8 as serialize};
+}
+
+/// Renders `serde_meta` like the attributes were written
+fn render_meta(items: &serde_json::Value) -> String {
+    items
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            let key = item["key"].as_str().unwrap();
+            match &item["value"] {
+                serde_json::Value::String(flag) if flag == "Flag" => key.to_string(),
+                value if value.get("Lit").is_some() => {
+                    format!("{key} = {:?}", value["Lit"]["value"].as_str().unwrap())
+                }
+                value => format!("{key}({})", render_meta(&value["List"]["items"])),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// One line for each container, field and variant with serde attributes
+fn serde_meta_lines(input: &serde_json::Value) -> String {
+    let mut lines = Vec::new();
+    let mut push = |path: String, named: &serde_json::Value| {
+        if named.get("serde_meta").is_some() {
+            lines.push(format!("{path}: {}", render_meta(&named["serde_meta"])));
+        }
+    };
+    let fields_of = |format: &serde_json::Value| {
+        format
+            .as_object()
+            .and_then(|format| format.values().next())
+            .and_then(|inner| inner.get("fields"))
+            .and_then(|fields| fields.as_array())
+            .cloned()
+            .unwrap_or_default()
+    };
+    let mut declarations = input["declarations"].as_array().unwrap().clone();
+    declarations.sort_by_key(|decl| decl["id"].as_str().unwrap().to_string());
+    for decl in declarations {
+        let id = decl["id"].as_str().unwrap();
+        push(id.to_string(), &decl);
+        for field in fields_of(&decl["container_kind"]) {
+            push(format!("{id}.{}", field["id"].as_str().unwrap()), &field);
+        }
+        for variant in decl["container_kind"]["Enum"]["variants"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            let variant_id = format!("{id}::{}", variant["id"].as_str().unwrap());
+            push(variant_id.clone(), variant);
+            for field in fields_of(&variant["variant_format"]) {
+                push(
+                    format!("{variant_id}.{}", field["id"].as_str().unwrap()),
+                    &field,
+                );
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+#[test]
+fn test_serde_attrs_matrix() {
+    let input: serde_json::Value =
+        serde_json::from_str(&Generation::for_tag("serde-matrix").to_input_json()).unwrap();
+    insta::assert_snapshot!(serde_meta_lines(&input), @r###"
+    Borrowed.name: borrow
+    Borrowed.other: borrow = "'a"
+    Container: rename = "ContainerRenamed", rename_all = "camelCase", deny_unknown_fields, bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"), expecting = "a container"
+    Container.renamed: rename(serialize = "ser_name", deserialize = "de_name"), alias = "a", alias = "b"
+    Container.count: default = "default_count", skip_serializing_if = "Option::is_none"
+    Container.defaulted: default
+    Container.custom: serialize_with = "ser_u8", deserialize_with = "de_u8"
+    Container.with_module: with = "u8_module"
+    Container.bounded: bound = "T: Clone"
+    Converted: from = "u8", into = "u8"
+    Defaulted: rename_all(serialize = "SCREAMING_SNAKE_CASE", deserialize = "snake_case"), default = "Defaulted::new", crate = "serde"
+    Defaulted.extra: flatten
+    FieldIdentifier: field_identifier, rename_all = "lowercase"
+    FieldIdentifier::Other: other
+    InternallyTagged: tag = "kind"
+    RemoteDef: remote = "remote::Remote"
+    RemoteDef.b: getter = "remote::Remote::b"
+    Transparent: transparent
+    TryConverted: try_from = "String"
+    Untagged: untagged
+    VariantIdentifier: variant_identifier
+    Variants: tag = "type", content = "content", rename_all = "snake_case"
+    Variants::First: rename(serialize = "first", deserialize = "one"), alias = "uno"
+    Variants::Second: rename_all = "UPPERCASE"
+    Variants::Skipped: skip
+    Variants::NotSerialized: skip_serializing
+    Variants::NotDeserialized: skip_deserializing
+    Variants::With: with = "u8_module"
+    Variants::Custom: serialize_with = "ser_u8", deserialize_with = "de_u8"
+    Variants::Bounded: bound = ""
+    Variants::Unknown: other
+    "###);
+}
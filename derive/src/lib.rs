@@ -7,13 +7,29 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, DeriveInput, ItemFn, Attribute};
+use syn::{parse_macro_input, DeriveInput, ItemFn};
 
 mod parse;
 
 // TODO: Support `#[codegen(crate = "wrapping_crate::derive_codegen")]`
 // https://github.com/dtolnay/linkme/blob/87e9f68b354421341eccb31c1f0dba0b63cc205d/impl/src/attr.rs#L4-L5
 /// Include this struct or enum in a retrievable metadata list in the `derive_codegen` crate.
+///
+/// Fields and variants with `#[codegen(tags = "a, b")]` are only included in generations
+/// selecting one of their tags.
+///
+/// `#[cfg]`s of fields and variants are kept, but the compiler removes an item's own `#[cfg]` before
+/// any macro sees it, so it's given with `#[codegen(cfg = "feature = \"admin\"")]` instead. The same
+/// goes for `#[fn_codegen]`, `#[const_codegen]` and `#[codegen]` items.
+///
+/// Attributes can be scoped to a generator, like `#[codegen(ts(as = "Date"))]`, or to tags,
+/// like `#[codegen(when(tag = "admin", rename = "x"))]`, and are resolved for each generation.
+/// A generator's scope can't be combined with its prefixed key on the same item, like `ts(as = "Date")`
+/// with `ts_as = "number"`.
+///
+/// Enums serialized as numbers by `serde_repr` need `#[codegen(serde_repr)]`, as `Serialize_repr`
+/// in the same `#[derive(...)]` as `Codegen` isn't visible to it. Unit-only enums with an integer
+/// `#[repr]` and no visible serde derive are described by their variant names, with a warning.
 #[proc_macro_derive(Codegen, attributes(codegen, serde))]
 pub fn derive_codegen(input: TokenStream) -> TokenStream {
     parse::derive(
@@ -28,6 +44,36 @@ pub fn derive_codegen(input: TokenStream) -> TokenStream {
 
 /// Submit functions to your code generator
 ///
+/// The arguments are the same as for `#[codegen(...)]`, such as `tags = "a, b"` (or `tag = "a"`).
+///
+/// `Result<T, E>` return types are also split into their success and error types. Aliases like
+/// `io::Result<T>` are recognized with `result_alias = "io::Result<io::Error>"`, where the generic
+/// argument is the error type, or `result_alias = "anyhow::Result"` to leave the error type out.
+/// `result_alias` on an `impl` block or trait with `#[codegen]` applies to all of its methods.
+///
+/// Returned futures like `impl Future<Output = T>` are unwrapped to their output, and stream
+/// parameters or returns like `impl Stream<Item = T>` or `mpsc::Receiver<T>` mark the function
+/// as server-, client- or bidirectional streaming. Channel receivers are only recognized with
+/// their module, like `mpsc::Receiver<T>` or `broadcast::Receiver<T>`.
+///
+/// With `extractors = "axum"` (or `"actix"`), web framework extractors like `Json(body): Json<CreateUser>`,
+/// `Path<T>`, `Query<T>`, `TypedHeader<T>` or `State<T>` are unwrapped and marked with where they come
+/// from in a request. Other extractors are added with `extractor = "Cbor = body"`, where the source is
+/// one of `body`, `path`, `query`, `header` or `state`. Both also apply to every method of an `impl`
+/// block or trait with `#[codegen]`, or every function of a `mod` with `#[codegen]`.
+///
+/// HTTP routes are given with `method = "POST", path = "/users/{id}"`, or read from framework
+/// attributes like `#[post("/users/{id}")]`, `#[actix_web::post(...)]` or `#[rocket::post(...)]`. Placeholders of the path are checked against the
+/// `Path` extractors of the function, or against its parameter names if it has none.
+/// Framework attributes are only seen when they're below `#[fn_codegen]`, since an attribute
+/// macro only receives the attributes which come after it.
+///
+/// With the `dispatch` flag, the function can also be called by name with JSON arguments through
+/// `derive_codegen::Dispatcher`. Its parameters must implement `Deserialize` (or be references to
+/// types whose owned form does), and its result must implement `Serialize`. Returned errors are
+/// serialized too, except for `result_alias`es without an error type, which use their `Display`.
+/// Functions with the same name in different modules are called by their path, like `my_crate::api::greet`.
+///
 /// example:
 /// ```rs
 /// #[fn_codegen(tag = "my-tag")]
@@ -44,14 +90,12 @@ pub fn derive_codegen(input: TokenStream) -> TokenStream {
 /// ```
 #[proc_macro_attribute]
 pub fn fn_codegen(
-    // TODO: enable parsing this as part of the codegen attributes
-    _attribute: proc_macro::TokenStream,
+    attribute: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let function = parse_macro_input!(item as ItemFn);
-    // Hmm: Could we have a general `#[codegen]` attr macro that auto-detects the kind of item and infers the derive stuff, etc?
-    // Would you need to discern when there are multiple `#[codegen]` things? What would happen if the codegen is on a field?
     let generated = parse::item_fn(
+        attribute.into(),
         function.clone(),
         parse::LinkKind::External {
             crate_name: "derive_codegen",
@@ -62,22 +106,121 @@ pub fn fn_codegen(
     // output.extend(quote::quote! {
     //     type _ = #ident;
     // });
-    proc_macro2::TokenStream::from(quote::quote! {
+    quote::quote! {
          #function
 
          #generated
-    })
+    }
+    .into()
+}
+
+/// Submit `const` and `static` items to your code generator, along with their serialized values.
+/// The item's type must implement `Serialize`.
+///
+/// The arguments are the same as for `#[codegen(...)]`.
+///
+/// example:
+/// ```rs
+/// #[const_codegen(tags = "my-tag")]
+/// pub const MAX_ITEMS: usize = 100;
+/// #[const_codegen(tags = "my-tag")]
+/// pub static FEATURES: &[&str] = &["search", "export"];
+/// ```
+#[proc_macro_attribute]
+pub fn const_codegen(
+    attribute: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let constant = parse_macro_input!(item as syn::Item);
+    let generated = parse::item_const(
+        attribute.into(),
+        constant.clone(),
+        parse::LinkKind::External {
+            crate_name: "derive_codegen",
+        },
+    )
+    .unwrap_or_else(|err| err.to_compile_error());
+    quote::quote! {
+         #constant
+
+         #generated
+    }
     .into()
 }
 
 /// Necessary to attach attributes outside the context of
 /// a derivation.
+///
+/// On `type` items, this submits the alias to your code generator,
+/// on `impl` blocks, their public methods (or those marked `#[codegen]`),
+/// and on `trait`s, their method signatures. Each `impl` block has its own id, like `UserServiceImpl`
+/// for `impl UserService` or `UserServiceHandlerImpl` for `impl Handler for UserService`.
+///
+/// On inline `mod`s, the arguments are defaults for every item inside with
+/// `#[derive(Codegen)]`, `#[fn_codegen]`, `#[const_codegen]` or `#[codegen]`, including in
+/// nested modules. Keys which an item (or a nested module) sets itself take precedence.
+///
+/// example:
+/// ```rs
+/// #[codegen(tags = "my-tag")]
+/// pub type UserId = String;
+/// #[codegen(tags = "my-tag")]
+/// impl UserService {
+///     pub fn find(&self, id: UserId) -> Option<User> {
+///         None
+///     }
+/// }
+/// #[codegen(tags = "my-tag")]
+/// pub trait UserApi {
+///     async fn get(&self, id: UserId) -> Result<User, ApiError>;
+/// }
+/// #[codegen(tags = "api", package = "api")]
+/// mod api {
+///     #[derive(Codegen)]
+///     pub struct User { name: String }
+///     #[derive(Codegen)]
+///     #[codegen(package = "admin")]
+///     pub struct Admin { name: String }
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn codegen(
-    _attributes: proc_macro::TokenStream,
+    attributes: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    return item;
+    match syn::parse::<syn::Item>(item.clone()) {
+        Ok(syn::Item::Type(alias)) => parse::item_type(
+            attributes.into(),
+            alias,
+            parse::LinkKind::External {
+                crate_name: "derive_codegen",
+            },
+        )
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into(),
+        Ok(syn::Item::Impl(block)) => parse::item_impl(
+            attributes.into(),
+            block,
+            parse::LinkKind::External {
+                crate_name: "derive_codegen",
+            },
+        )
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into(),
+        Ok(syn::Item::Trait(interface)) => parse::item_trait(
+            attributes.into(),
+            interface,
+            parse::LinkKind::External {
+                crate_name: "derive_codegen",
+            },
+        )
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into(),
+        Ok(syn::Item::Mod(module)) => parse::item_mod(attributes.into(), module)
+            .unwrap_or_else(|err| err.to_compile_error())
+            .into(),
+        _ => item,
+    }
 }
 
 #[doc(hidden)]
@@ -1,9 +1,11 @@
 use i_codegen_code::{types as st, utils};
 use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
 
 // use crate::attr;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
+use syn::spanned::Spanned as _;
 use syn::{DeriveInput, Ident, Result};
 
 pub enum LinkKind {
@@ -11,14 +13,31 @@ pub enum LinkKind {
     /// This can be used for other crates to encapsulate derive-codegen, where they can re-export
     /// the appropriate linkme stuff.
     External {
-        /// The encapsulating crate's name as seen during a `use [crate_name]::{linkme, Context, CODEGEN_ITEMS};` statement.
+        /// The encapsulating crate's name as seen during a `use [crate_name]::{linkme, serde_json, Context, RuntimeValues, CODEGEN_ITEMS};` statement.
         crate_name: &'static str,
     },
 }
 
+impl LinkKind {
+    // This may wrongly give a rust analyzer warning about "snake case" because of https://github.com/rust-lang/rust-analyzer/issues/6541
+    fn crate_ident(&self) -> Ident {
+        match self {
+            LinkKind::Internal => Ident::new("i_codegen_code", Span::call_site()),
+            LinkKind::External { crate_name } => Ident::new(crate_name, Span::call_site()),
+        }
+    }
+}
+
 /// see [i_codegen_code::Context]
 pub fn derive(input: DeriveInput, kind: LinkKind) -> Result<TokenStream> {
     let ident: &Ident = &input.ident;
+    let capture_default = has_codegen_flag(&input.attrs, "capture_default");
+    if capture_default && !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`#[codegen(capture_default)]` is not supported for generic types",
+        ));
+    }
     let ctxt = Ctxt::new();
 
     let container = ast::Container::from_ast(&ctxt, &input, Derive::Serialize)
@@ -29,11 +48,8 @@ pub fn derive(input: DeriveInput, kind: LinkKind) -> Result<TokenStream> {
     // // if we don't consume the errors, we'll get an "unhandled errors" panic whether or not there were errors
     // ctxt.check().unwrap();
 
-    let mut pctxt = ParseContext {
-        serde_container_ctxt: Some(ctxt),
-        ident: ident.clone(),
-        publish_builtins: Default::default(),
-    };
+    let mut pctxt = ParseContext::new(ident, Some(ctxt));
+    pctxt.capture_default = capture_default;
 
     let container_format = match container.data {
         ast::Data::Enum(ref variants) => pctxt.derive_enum(variants, &container),
@@ -43,6 +59,7 @@ pub fn derive(input: DeriveInput, kind: LinkKind) -> Result<TokenStream> {
     let mut root = st::TypeRoot {
         file: "unknown".to_string(),
         line: 0,
+        module_path: String::new(),
         inner: pctxt.derive_named(
             st::RootItem::Container(container_format),
             ident,
@@ -50,70 +67,686 @@ pub fn derive(input: DeriveInput, kind: LinkKind) -> Result<TokenStream> {
             Some(&container),
         ),
         extras: Vec::new(),
+        doc_includes: Vec::new(),
+        captured_defaults: Vec::new(),
+        value: None,
+        warnings: Vec::new(),
     };
+    root.inner.rust_attrs.visibility = Some(visibility_to_string(&input.vis, ident.span()));
+    if capture_default {
+        let default = match container.attrs.default() {
+            attr::Default::Path(path) => quote!(#path()),
+            attr::Default::Default | attr::Default::None => {
+                quote!(<#ident as ::core::default::Default>::default())
+            }
+        };
+        root.inner.captured_default = Some(pctxt.captured_defaults.len());
+        pctxt.captured_defaults.push(default);
+    }
 
     for (_builtin_id, named_container_format) in pctxt.publish_builtins.drain() {
         root.extras.push(named_container_format);
     }
 
-    create_linkme_tokens_from_type_root(root, kind, ident)
+    create_linkme_tokens_from_type_root(root, kind, ident, &pctxt)
 }
 
 /// see [i_codegen_code::Context]
-pub fn item_fn(input: syn::ItemFn, kind: LinkKind) -> Result<TokenStream> {
+pub fn item_fn(attribute: TokenStream, input: syn::ItemFn, kind: LinkKind) -> Result<TokenStream> {
     let ident: &Ident = &input.sig.ident;
+    let attrs = attrs_with_codegen_args(attribute, &input.attrs);
 
-    let mut pctxt = ParseContext {
-        serde_container_ctxt: None,
-        ident: ident.clone(),
-        publish_builtins: Default::default(),
+    let mut pctxt = ParseContext::new(ident, None);
+    let mut fn_format = pctxt.signature_to_function_format(&input.sig, &attrs)?;
+    fn_format.route = function_route(&attrs, &input.sig, &fn_format)?;
+
+    let mut root = st::TypeRoot {
+        file: "unknown".to_string(),
+        line: 0,
+        module_path: String::new(),
+        inner: pctxt.derive_named(st::RootItem::Function(fn_format), ident, &attrs, None),
+        extras: Vec::new(),
+        doc_includes: Vec::new(),
+        captured_defaults: Vec::new(),
+        value: None,
+        warnings: Vec::new(),
     };
+    root.inner.rust_generics = type_generics(&input.sig.generics);
+    root.inner.rust_attrs.visibility = Some(visibility_to_string(&input.vis, ident.span()));
 
-    let mut self_opt = None;
-    let params = input
-        .sig
-        .inputs
+    for (_builtin_id, named_container_format) in pctxt.publish_builtins.drain() {
+        root.extras.push(named_container_format);
+    }
+
+    let dispatch = match &root.inner.value {
+        st::RootItem::Function(fn_format) if has_codegen_flag(&attrs, "dispatch") => {
+            create_dispatch_tokens(&input, fn_format, &root_tags(&root), &kind)?
+        }
+        _ => TokenStream::new(),
+    };
+    let generated = create_linkme_tokens_from_type_root(root, kind, ident, &pctxt)?;
+    Ok(quote! {
+        #generated
+
+        #dispatch
+    })
+}
+
+/// see [i_codegen_code::Context]
+pub fn item_const(attribute: TokenStream, input: syn::Item, kind: LinkKind) -> Result<TokenStream> {
+    let (attrs, vis, ident, ty, is_static) = match &input {
+        syn::Item::Const(item) => (&item.attrs, &item.vis, &item.ident, &item.ty, false),
+        syn::Item::Static(item) => {
+            if let syn::StaticMutability::Mut(mutability) = item.mutability {
+                return Err(syn::Error::new(
+                    mutability.span,
+                    "`static mut` items can't be read safely to be registered",
+                ));
+            }
+            (&item.attrs, &item.vis, &item.ident, &item.ty, true)
+        }
+        other => {
+            return Err(syn::Error::new_spanned(
+                other,
+                "expected a `const` or `static` item",
+            ))
+        }
+    };
+
+    let attrs = attrs_with_codegen_args(attribute, attrs);
+
+    let mut pctxt = ParseContext::new(ident, None);
+    let constant_format = st::ConstantFormat {
+        is_static,
+        format: pctxt.type_to_format(ty),
+    };
+
+    let mut root = st::TypeRoot {
+        file: "unknown".to_string(),
+        line: 0,
+        module_path: String::new(),
+        inner: pctxt.derive_named(st::RootItem::Constant(constant_format), ident, &attrs, None),
+        extras: Vec::new(),
+        doc_includes: Vec::new(),
+        captured_defaults: Vec::new(),
+        value: None,
+        warnings: Vec::new(),
+    };
+    root.inner.rust_attrs.visibility = Some(visibility_to_string(vis, ident.span()));
+    pctxt.value = Some(quote!(#ident));
+
+    for (_builtin_id, named_container_format) in pctxt.publish_builtins.drain() {
+        root.extras.push(named_container_format);
+    }
+
+    create_linkme_tokens_from_type_root(root, kind, ident, &pctxt)
+}
+
+/// The `#[codegen]` attribute on a `trait`, whose arguments are in `attribute`.
+pub fn item_trait(
+    attribute: TokenStream,
+    mut input: syn::ItemTrait,
+    kind: LinkKind,
+) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let attrs = take_codegen_attrs(attribute, &mut input.attrs);
+
+    let mut pctxt = ParseContext::new(ident, None);
+    pctxt.generic_params = type_generic_names(&input.generics);
+    pctxt.result_aliases = codegen_result_aliases(&attrs)?;
+    pctxt.extractors = codegen_extractors(&attrs)?;
+    pctxt.frameworks = codegen_frameworks(&attrs)?;
+    let methods = input
+        .items
         .iter()
-        .enumerate()
-        .filter_map(|(idx, arg)| match arg {
-            syn::FnArg::Receiver(recv) => {
-                let format = pctxt.type_to_format(&recv.ty);
-                let ident = syn::Ident::new("self", recv.self_token.span);
-                self_opt = Some(pctxt.derive_named(format, &ident, &recv.attrs, None));
-                None
+        .filter_map(|item| match item {
+            syn::TraitItem::Fn(method) => Some(method),
+            _ => None,
+        })
+        .map(|method| {
+            let fn_format = pctxt.signature_to_function_format(&method.sig, &method.attrs)?;
+            let mut named = pctxt.derive_named(fn_format, &method.sig.ident, &method.attrs, None);
+            named.rust_generics = type_generics(&method.sig.generics);
+            Ok(named)
+        })
+        .collect::<Result<_>>()?;
+    let interface_format = st::InterfaceFormat { methods };
+
+    let mut root = st::TypeRoot {
+        file: "unknown".to_string(),
+        line: 0,
+        module_path: String::new(),
+        inner: pctxt.derive_named(
+            st::RootItem::Interface(interface_format),
+            ident,
+            &attrs,
+            None,
+        ),
+        extras: Vec::new(),
+        doc_includes: Vec::new(),
+        captured_defaults: Vec::new(),
+        value: None,
+        warnings: Vec::new(),
+    };
+    root.inner.rust_generics = type_generics(&input.generics);
+    root.inner.rust_attrs.visibility = Some(visibility_to_string(&input.vis, ident.span()));
+
+    for (_builtin_id, named_container_format) in pctxt.publish_builtins.drain() {
+        root.extras.push(named_container_format);
+    }
+
+    let generated = create_linkme_tokens_from_type_root(root, kind, ident, &pctxt)?;
+    Ok(quote! {
+        #input
+
+        #generated
+    })
+}
+
+/// The `#[codegen]` attribute on an `impl` block, whose arguments are in `attribute`.
+pub fn item_impl(
+    attribute: TokenStream,
+    mut input: syn::ItemImpl,
+    kind: LinkKind,
+) -> Result<TokenStream> {
+    let self_ident = match &*input.self_ty {
+        syn::Type::Path(syn::TypePath { path, .. }) => match path.segments.last() {
+            Some(segment) => segment.ident.clone(),
+            None => return Err(syn::Error::new_spanned(path, "expected a type name")),
+        },
+        other => {
+            return Err(syn::Error::new_spanned(
+                other,
+                "`#[codegen]` is only supported on `impl` blocks for named types",
+            ))
+        }
+    };
+    let attrs = take_codegen_attrs(attribute, &mut input.attrs);
+
+    let mut pctxt = ParseContext::new(&self_ident, None);
+    pctxt.self_type = Some((*input.self_ty).clone());
+    pctxt.generic_params = type_generic_names(&input.generics);
+    pctxt.result_aliases = codegen_result_aliases(&attrs)?;
+    pctxt.extractors = codegen_extractors(&attrs)?;
+    pctxt.frameworks = codegen_frameworks(&attrs)?;
+    let is_trait_impl = input.trait_.is_some();
+    let methods = input
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::ImplItem::Fn(method)
+                if is_trait_impl
+                    || !matches!(method.vis, syn::Visibility::Inherited)
+                    || method.attrs.iter().any(is_codegen_attr) =>
+            {
+                Some(method)
             }
-            syn::FnArg::Typed(pat) => Some(pctxt.pattern_to_named_format(&*pat, idx)),
+            _ => None,
         })
-        .collect();
-    let fn_format = st::FunctionFormat {
-        is_async: input.sig.asyncness.is_some(),
-        self_opt,
-        params,
-        ret: Box::new(match input.sig.output {
-            syn::ReturnType::Default => st::Format::Unit,
-            syn::ReturnType::Type(_, ttype) => pctxt.type_to_format(&ttype),
-        }),
+        .map(|method| {
+            let fn_format = pctxt.signature_to_function_format(&method.sig, &method.attrs)?;
+            let mut named = pctxt.derive_named(fn_format, &method.sig.ident, &method.attrs, None);
+            named.rust_generics = type_generics(&method.sig.generics);
+            named.rust_attrs.visibility =
+                Some(visibility_to_string(&method.vis, method.sig.ident.span()));
+            Ok(named)
+        })
+        .collect::<Result<_>>()?;
+    let service_format = st::ServiceFormat {
+        self_type: pctxt.type_to_format(&input.self_ty),
+        trait_type: input
+            .trait_
+            .as_ref()
+            .map(|(_, path, _)| pctxt.type_to_format(&syn::parse_quote!(#path))),
+        methods,
+    };
+
+    // distinct from the type's own declaration and from its other impl blocks,
+    // e.g. `UserStoreImpl` for `impl UserStore` and `UserStoreDescribeImpl` for `impl Describe for UserStore`
+    let trait_ident = input
+        .trait_
+        .as_ref()
+        .and_then(|(_, path, _)| path.segments.last())
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default();
+    let mut root = st::TypeRoot {
+        file: "unknown".to_string(),
+        line: 0,
+        module_path: String::new(),
+        inner: pctxt.derive_named_at(
+            st::RootItem::Service(service_format),
+            format!("{self_ident}{trait_ident}Impl"),
+            self_ident.span(),
+            &attrs,
+            None,
+        ),
+        extras: Vec::new(),
+        doc_includes: Vec::new(),
+        captured_defaults: Vec::new(),
+        value: None,
+        warnings: Vec::new(),
     };
+    root.inner.rust_generics = type_generics(&input.generics);
+
+    for (_builtin_id, named_container_format) in pctxt.publish_builtins.drain() {
+        root.extras.push(named_container_format);
+    }
+
+    // a type may have several impl blocks, besides its own derive
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    quote!(#input).to_string().hash(&mut hasher);
+    let dummy_ident = Ident::new(
+        &format!("impl_{self_ident}_{:x}", hasher.finish()),
+        self_ident.span(),
+    );
+    let generated = create_linkme_tokens_from_type_root(root, kind, &dummy_ident, &pctxt)?;
+    Ok(quote! {
+        #input
+
+        #generated
+    })
+}
+
+/// The `#[codegen]` attribute on a `type` item, whose arguments are in `attribute`.
+pub fn item_type(
+    attribute: TokenStream,
+    mut input: syn::ItemType,
+    kind: LinkKind,
+) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let attrs = take_codegen_attrs(attribute, &mut input.attrs);
+
+    let mut pctxt = ParseContext::new(ident, None);
+    let target = pctxt.type_to_format(&input.ty);
 
     let mut root = st::TypeRoot {
         file: "unknown".to_string(),
         line: 0,
-        inner: pctxt.derive_named(st::RootItem::Function(fn_format), ident, &input.attrs, None),
+        module_path: String::new(),
+        inner: pctxt.derive_named(st::RootItem::Alias(target), ident, &attrs, None),
         extras: Vec::new(),
+        doc_includes: Vec::new(),
+        captured_defaults: Vec::new(),
+        value: None,
+        warnings: Vec::new(),
     };
+    root.inner.rust_generics = type_generics(&input.generics);
+    root.inner.rust_attrs.visibility = Some(visibility_to_string(&input.vis, ident.span()));
 
     for (_builtin_id, named_container_format) in pctxt.publish_builtins.drain() {
         root.extras.push(named_container_format);
     }
 
-    create_linkme_tokens_from_type_root(root, kind, ident)
+    let generated = create_linkme_tokens_from_type_root(root, kind, ident, &pctxt)?;
+    Ok(quote! {
+        #input
+
+        #generated
+    })
+}
+
+/// Every `tags` occurrence, e.g. `#[codegen(tags = "a, b")]` and `#[fn_codegen(tag = "c")]`
+fn root_tags(root: &st::TypeRoot) -> Vec<syn::LitStr> {
+    root.inner
+        .codegen_tags()
+        .into_iter()
+        .map(|tag| syn::LitStr::new(tag, Span::call_site()))
+        .collect()
+}
+
+/// For `#[fn_codegen(dispatch)]`, a shim which calls the function with JSON arguments,
+/// registered in [i_codegen_code::dispatch::CODEGEN_DISPATCH].
+fn create_dispatch_tokens(
+    input: &syn::ItemFn,
+    fn_format: &st::FunctionFormat,
+    tags: &[syn::LitStr],
+    kind: &LinkKind,
+) -> Result<TokenStream> {
+    let ident = &input.sig.ident;
+    if !input.sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.sig.generics,
+            "`dispatch` is not supported for generic functions",
+        ));
+    }
+    if let Some(extractor) = fn_format.extractors.first() {
+        return Err(syn::Error::new_spanned(
+            &input.sig.inputs[extractor.param],
+            "`dispatch` is not supported for functions with extractor parameters",
+        ));
+    }
+    let crate_q = kind.crate_ident();
+    let names = fn_format.params.iter().map(|param| &param.rust_ident.value);
+
+    let mut takes = Vec::new();
+    let mut args = Vec::new();
+    for (idx, arg) in input.sig.inputs.iter().enumerate() {
+        let ty = match arg {
+            syn::FnArg::Typed(pat) => &*pat.ty,
+            syn::FnArg::Receiver(recv) => {
+                return Err(syn::Error::new_spanned(
+                    recv,
+                    "`dispatch` is not supported for methods",
+                ))
+            }
+        };
+        let var = Ident::new(&format!("arg{idx}"), Span::call_site());
+        // e.g. a `String` for `&str`, to pass a reference to
+        match ty {
+            syn::Type::Reference(syn::TypeReference {
+                elem, mutability, ..
+            }) => {
+                takes.push(quote! {
+                    let #mutability #var: <#elem as ::std::borrow::ToOwned>::Owned = args.take(#idx)?;
+                });
+                args.push(quote!(&#mutability #var));
+            }
+            _ => {
+                takes.push(quote!(let #var: #ty = args.take(#idx)?;));
+                args.push(quote!(#var));
+            }
+        }
+    }
+    let awaited = if fn_format.is_async || fn_format.ret_future_output.is_some() {
+        quote!(.await)
+    } else {
+        quote!()
+    };
+    let output = match (&fn_format.ret_ok, &fn_format.ret_err) {
+        (Some(_), Some(_)) => quote! {
+            match ret {
+                Ok(value) => ::#crate_q::dispatch::to_output(&value),
+                Err(err) => Err(::#crate_q::dispatch::to_failure(&err)),
+            }
+        },
+        // e.g. `anyhow::Result<T>`
+        (Some(_), None) => quote! {
+            match ret {
+                Ok(value) => ::#crate_q::dispatch::to_output(&value),
+                Err(err) => Err(::#crate_q::dispatch::to_failure_message(&err)),
+            }
+        },
+        _ => quote!(::#crate_q::dispatch::to_output(&ret)),
+    };
+
+    let shim = Ident::new(
+        &format!("_DERIVE_CODEGEN_DISPATCH_SHIM_FOR_{ident}"),
+        Span::call_site(),
+    );
+    let entry = Ident::new(
+        &format!("_DERIVE_CODEGEN_DISPATCH_FOR_{ident}"),
+        Span::call_site(),
+    );
+    let name = syn::LitStr::new(&ident.to_string(), ident.span());
+    Ok(quote! {
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        fn #shim(args: ::#crate_q::serde_json::Value) -> ::#crate_q::dispatch::DispatchFuture {
+            ::std::boxed::Box::pin(async move {
+                let mut args = ::#crate_q::dispatch::Args::new(args, &[#(#names,)*])?;
+                #(#takes)*
+                let ret = #ident(#(#args),*)#awaited;
+                #output
+            })
+        }
+
+        #[doc(hidden)]
+        #[allow(non_upper_case_globals)]
+        #[::#crate_q::linkme::distributed_slice(::#crate_q::dispatch::CODEGEN_DISPATCH)]
+        #[linkme(crate = ::#crate_q::linkme)]
+        static #entry: ::#crate_q::dispatch::DispatchFn = ::#crate_q::dispatch::DispatchFn {
+            name: #name,
+            module_path: module_path!(),
+            tags: &[#(#tags,)*],
+            invoke: #shim,
+        };
+    })
+}
+
+/// The `#[codegen]` attribute on an inline `mod`, whose arguments are defaults for the items inside it,
+/// like `#[codegen(tags = "api")]`. Keys which an item sets itself (with `tag` and `tags` being the same)
+/// are left out of its defaults, each key of a scope like `ts(as = "Date")` on its own, and nested modules
+/// with `#[codegen(...)]` override the defaults for theirs.
+pub fn item_mod(attribute: TokenStream, mut input: syn::ItemMod) -> Result<TokenStream> {
+    let defaults = parse_codegen_args(attribute)?;
+    match &mut input.content {
+        Some((_, items)) => apply_mod_defaults(&defaults, items)?,
+        None => {
+            return Err(syn::Error::new_spanned(
+                &input,
+                "`#[codegen]` is only supported on inline modules",
+            ))
+        }
+    }
+    Ok(quote!(#input))
+}
+
+/// The arguments of `#[codegen(...)]`, which like [syn::meta::parser] allows keys like `as` in `ts(as = "Date")`
+fn parse_codegen_args(tokens: TokenStream) -> Result<Vec<syn::Meta>> {
+    let mut args = Vec::new();
+    syn::parse::Parser::parse2(
+        syn::meta::parser(|meta| {
+            let path = meta.path.clone();
+            args.push(if meta.input.peek(syn::token::Paren) {
+                let content;
+                let paren = syn::parenthesized!(content in meta.input);
+                syn::Meta::List(syn::MetaList {
+                    path,
+                    delimiter: syn::MacroDelimiter::Paren(paren),
+                    tokens: content.parse()?,
+                })
+            } else if meta.input.peek(syn::Token![=]) {
+                syn::Meta::NameValue(syn::MetaNameValue {
+                    path,
+                    eq_token: meta.input.parse()?,
+                    value: meta.input.parse()?,
+                })
+            } else {
+                syn::Meta::Path(path)
+            });
+            Ok(())
+        }),
+        tokens,
+    )?;
+    Ok(args)
+}
+
+/// e.g. `"tags"` for `tags = "a"`, `tag = "a"` or `tags`
+fn meta_key(meta: &syn::Meta) -> String {
+    match path_to_compact_string(meta.path()) {
+        key if key == "tag" => "tags".to_string(),
+        key => key,
+    }
+}
+
+/// Splits an argument of `#[codegen(...)]` into single settings with their keys, so a scope like
+/// `ts(as = "Date", optional)` gives `ts(as = "Date")` keyed `ts_as`, the same as its prefixed form,
+/// and `ts(optional)` keyed `ts_optional`. The tags of `when(tag = "a", ...)` stay with each setting.
+fn split_meta(meta: &syn::Meta) -> Result<Vec<(String, syn::Meta)>> {
+    let syn::Meta::List(list) = meta else {
+        return Ok(vec![(meta_key(meta), meta.clone())]);
+    };
+    let path = &list.path;
+    let nested = parse_codegen_args(list.tokens.clone())?;
+    let (conditions, settings): (Vec<_>, Vec<_>) = nested.into_iter().partition(|nested| {
+        path.is_ident("when") && (nested.path().is_ident("tag") || nested.path().is_ident("tags"))
+    });
+    let scope = if path.is_ident("when") {
+        let mut tags = Vec::new();
+        for condition in &conditions {
+            if let syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }),
+                ..
+            }) = condition
+            {
+                tags.extend(lit.value().split(',').map(|tag| tag.trim().to_string()));
+            }
+        }
+        tags.sort();
+        format!("when[{}]", tags.join(","))
+    } else {
+        path_to_compact_string(path)
+    };
+    let mut split = Vec::new();
+    for setting in &settings {
+        for (key, inner) in split_meta(setting)? {
+            split.push((
+                format!("{scope}_{key}"),
+                syn::Meta::List(syn::MetaList {
+                    path: path.clone(),
+                    delimiter: list.delimiter.clone(),
+                    tokens: quote!(#(#conditions,)* #inner),
+                }),
+            ));
+        }
+    }
+    Ok(split)
+}
+
+fn apply_mod_defaults(defaults: &[syn::Meta], items: &mut [syn::Item]) -> Result<()> {
+    /// Keys which the item sets in its own `#[codegen(...)]` attributes, or in `macro_attr`
+    fn own_keys(
+        attrs: &[syn::Attribute],
+        macro_attr: Option<&syn::Attribute>,
+    ) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        for attr in attrs
+            .iter()
+            .filter(|attr| is_codegen_attr(attr))
+            .chain(macro_attr)
+        {
+            if let syn::Meta::List(list) = &attr.meta {
+                for meta in &parse_codegen_args(list.tokens.clone())? {
+                    keys.extend(split_meta(meta)?.into_iter().map(|(key, _)| key));
+                }
+            }
+        }
+        Ok(keys)
+    }
+    fn missing(defaults: &[syn::Meta], keys: &[String]) -> Result<Vec<syn::Meta>> {
+        let mut missing = Vec::new();
+        for meta in defaults {
+            missing.extend(
+                split_meta(meta)?
+                    .into_iter()
+                    .filter(|(key, _)| !keys.contains(key))
+                    .map(|(_, meta)| meta),
+            );
+        }
+        Ok(missing)
+    }
+    /// Prepends the defaults to the arguments of an attribute macro like `#[fn_codegen(...)]`
+    fn prepend_args(attr: &mut syn::Attribute, defaults: &[syn::Meta]) {
+        if defaults.is_empty() {
+            return;
+        }
+        let path = attr.path().clone();
+        attr.meta = match &attr.meta {
+            syn::Meta::List(list) => {
+                let args = &list.tokens;
+                syn::parse_quote!(#path(#(#defaults,)* #args))
+            }
+            _ => syn::parse_quote!(#path(#(#defaults),*)),
+        };
+    }
+    let attr_named = |attrs: &[syn::Attribute], name: &str| {
+        attrs.iter().position(|attr| {
+            attr.path()
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == name)
+        })
+    };
+
+    for item in items {
+        let (attrs, macro_name) = match item {
+            syn::Item::Struct(syn::ItemStruct { attrs, .. })
+            | syn::Item::Enum(syn::ItemEnum { attrs, .. })
+            | syn::Item::Union(syn::ItemUnion { attrs, .. }) => {
+                let derives_codegen = attrs.iter().any(|attr| {
+                    attr.path().is_ident("derive")
+                        && attr
+                            .parse_args_with(
+                                syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+                            )
+                            .is_ok_and(|paths| {
+                                paths.iter().any(|path| {
+                                    path.segments.last().is_some_and(|segment| {
+                                        segment.ident == "Codegen" || segment.ident == "CodegenInternal"
+                                    })
+                                })
+                            })
+                });
+                if derives_codegen {
+                    // as a helper attribute of the derive
+                    let defaults = missing(defaults, &own_keys(attrs, None)?)?;
+                    if !defaults.is_empty() {
+                        attrs.push(syn::parse_quote!(#[codegen(#(#defaults),*)]));
+                    }
+                }
+                continue;
+            }
+            syn::Item::Mod(syn::ItemMod {
+                attrs,
+                content: Some((_, items)),
+                ..
+            }) => {
+                match attr_named(attrs, "codegen") {
+                    Some(idx) => {
+                        // handled here rather than by its own expansion, to override the defaults
+                        let attr = attrs.remove(idx);
+                        let mut nested = match &attr.meta {
+                            syn::Meta::List(list) => parse_codegen_args(list.tokens.clone())?,
+                            _ => Vec::new(),
+                        };
+                        let mut keys = Vec::new();
+                        for meta in &nested {
+                            keys.extend(split_meta(meta)?.into_iter().map(|(key, _)| key));
+                        }
+                        nested.extend(missing(defaults, &keys)?);
+                        apply_mod_defaults(&nested, items)?;
+                    }
+                    None => apply_mod_defaults(defaults, items)?,
+                }
+                continue;
+            }
+            syn::Item::Fn(syn::ItemFn { attrs, .. }) => (attrs, "fn_codegen"),
+            syn::Item::Const(syn::ItemConst { attrs, .. })
+            | syn::Item::Static(syn::ItemStatic { attrs, .. }) => (attrs, "const_codegen"),
+            syn::Item::Type(syn::ItemType { attrs, .. })
+            | syn::Item::Impl(syn::ItemImpl { attrs, .. })
+            | syn::Item::Trait(syn::ItemTrait { attrs, .. }) => (attrs, "codegen"),
+            _ => continue,
+        };
+        if let Some(idx) = attr_named(attrs, macro_name) {
+            let others = attrs
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != idx)
+                .map(|(_, attr)| attr.clone())
+                .collect::<Vec<_>>();
+            let keys = own_keys(&others, Some(&attrs[idx]))?;
+            prepend_args(&mut attrs[idx], &missing(defaults, &keys)?);
+        }
+    }
+    Ok(())
 }
 
 fn create_linkme_tokens_from_type_root(
     root: st::TypeRoot,
     kind: LinkKind,
     ident: &Ident,
+    pctxt: &ParseContext,
 ) -> Result<TokenStream> {
+    let mut errors = pctxt.errors.iter().cloned();
+    if let Some(mut error) = errors.next() {
+        error.extend(errors);
+        return Err(error);
+    }
     let dummy = Ident::new(
         &format!("_DERIVE_CODEGEN_PARSED_FOR_{}", ident),
         Span::call_site(),
@@ -121,24 +754,34 @@ fn create_linkme_tokens_from_type_root(
     let type_root_json: String = serde_json::to_string(&root).expect("serialize type root");
     let type_root_json_lit = syn::LitStr::new(&type_root_json, ident.span());
 
-    let q_tags = root
-        .inner
-        .codegen_attrs
-        .iter()
-        .flat_map(|attr| {
-            if attr.value.0.value == "tags" {
-                Some(attr.value.1.value.split(',').into_iter().map(String::from))
-            } else {
-                None
-            }
-        })
-        .flatten()
-        .map(|attr| syn::LitStr::new(&attr, Span::call_site()));
+    let q_tags = root_tags(&root);
+    let i_codegen_code_crate_q = kind.crate_ident();
 
-    // This may wrongly give a rust analyzer warning about "snake case" because of https://github.com/rust-lang/rust-analyzer/issues/6541
-    let i_codegen_code_crate_q = match kind {
-        LinkKind::Internal => Ident::new("i_codegen_code", Span::call_site()),
-        LinkKind::External { crate_name } => Ident::new(&crate_name, Span::call_site()),
+    let add_type_root_q = if pctxt.doc_includes.is_empty()
+        && pctxt.captured_defaults.is_empty()
+        && pctxt.value.is_none()
+    {
+        quote! {
+            context.add_type_root(#type_root_json_lit, file!(), line!(), module_path!(), &[#(#q_tags,)*]);
+        }
+    } else {
+        // evaluated here, since e.g. `include_str!` is relative to the file of the item,
+        // and default value paths are relative to its module
+        let doc_includes = &pctxt.doc_includes;
+        let captured_defaults = &pctxt.captured_defaults;
+        let value = match &pctxt.value {
+            Some(value) => {
+                quote!(Some(&|| ::#i_codegen_code_crate_q::serde_json::to_value(&#value)))
+            }
+            None => quote!(None),
+        };
+        quote! {
+            context.add_type_root_with(#type_root_json_lit, file!(), line!(), module_path!(), &[#(#q_tags,)*], ::#i_codegen_code_crate_q::RuntimeValues {
+                doc_includes: &[#(#doc_includes,)*],
+                captured_defaults: &[#(&|| ::#i_codegen_code_crate_q::serde_json::to_value(&#captured_defaults),)*],
+                value: #value,
+            });
+        }
     };
 
     Ok(quote! {
@@ -148,69 +791,22 @@ fn create_linkme_tokens_from_type_root(
         #[::#i_codegen_code_crate_q::linkme::distributed_slice(::#i_codegen_code_crate_q::CODEGEN_ITEMS)]
         #[linkme(crate = ::#i_codegen_code_crate_q::linkme)]
         fn #dummy(context: &mut ::#i_codegen_code_crate_q::Context) {
-            context.add_type_root(#type_root_json_lit, file!(), line!(), &[#(#q_tags,)*]);
+            #add_type_root_q
         }
     })
 }
 
-fn get_doc_comments(attrs: &[syn::Attribute]) -> Option<String> {
-    let docs = attrs
-        .iter()
-        .filter_map(|attr| {
-            if let ("doc", syn::Meta::NameValue(name_value)) =
-                (path_to_string(&attr.path()).as_str(), &attr.meta)
-            {
-                if let syn::Expr::Lit(syn::ExprLit {
-                    attrs: _,
-                    lit: syn::Lit::Str(s),
-                }) = &name_value.value
-                {
-                    Some(s.value())
-                } else {
-                    eprintln!("Unexpected {:?}", name_value);
-                    None
-                }
-            } else {
-                None
-            }
-        })
-        .filter_map(|doc_value| {
-            let text = doc_value
-                .trim_start_matches("//!")
-                .trim_start_matches("///")
-                .trim_start_matches("/*!")
-                .trim_start_matches("/**")
-                .trim_end_matches("*/")
-                .trim();
-            if text.is_empty() {
-                None
-            } else {
-                Some(text.to_string())
-            }
-        })
-        .collect::<Vec<_>>();
-
-    if docs.is_empty() {
-        None
-    } else {
-        Some(docs.join("\n"))
-    }
-}
-
 #[inline]
 fn path_to_string(path: &syn::Path) -> String {
     quote!(#path).to_string()
 }
 
-use serde_derive_internals::{ast, Ctxt, Derive};
+use serde_derive_internals::{ast, attr, Ctxt, Derive};
 
 fn field_type_name(ty: &syn::Type) -> Option<String> {
     use syn::Type::Path;
     match ty {
-        Path(syn::TypePath { path, .. }) => match path.segments.last() {
-            Some(t) => Some(t.ident.to_string()),
-            _ => None,
-        },
+        Path(syn::TypePath { path, .. }) => path.segments.last().map(|t| t.ident.to_string()),
         _ => None,
     }
 }
@@ -222,237 +818,1180 @@ fn is_phantom(ty: &syn::Type) -> bool {
     }
 }
 
-fn filter_visible<'a>(fields: &'a [ast::Field<'a>]) -> Vec<&'a ast::Field<'a>> {
-    let mut content: Vec<&'a ast::Field<'a>> = Vec::with_capacity(fields.len());
+fn filter_visible<'a>(fields: &'a [ast::Field<'a>]) -> Vec<&'a ast::Field<'a>> {
+    let mut content: Vec<&'a ast::Field<'a>> = Vec::with_capacity(fields.len());
+
+    for field in fields {
+        if field.attrs.skip_serializing() || is_phantom(field.ty) {
+            continue;
+        }
+
+        content.push(field);
+    }
+    content
+}
+
+impl<'a> ParseContext {
+    pub(crate) fn derive_enum(
+        &mut self,
+        variants: &[ast::Variant<'a>],
+        container: &ast::Container,
+    ) -> st::ContainerFormat {
+        let mut map = BTreeMap::<u32, st::Named<st::VariantFormat>>::new();
+        // implicit discriminants count up from the previous variant's
+        let mut next_discriminant = Some(0i128);
+        for (idx, variant) in variants.iter().enumerate() {
+            let inner: st::VariantFormat = match variant.style {
+                ast::Style::Struct => st::VariantFormat::Struct(
+                    self.derive_named_fields_alt(&variant.fields).collect(),
+                ),
+                ast::Style::Tuple => st::VariantFormat::Tuple(
+                    self.derive_named_fields_alt(&variant.fields).collect(),
+                ),
+                ast::Style::Newtype => {
+                    st::VariantFormat::NewType(Box::new(self.field_to_format(&variant.fields[0])))
+                }
+                ast::Style::Unit => st::VariantFormat::Unit,
+            };
+            let discriminant = match variant.original.discriminant {
+                Some((_, ref expr)) => {
+                    eval_discriminant(expr).map(|value| spanned(&[expr.span()], value))
+                }
+                None => next_discriminant.map(|value| spanned(&[variant.ident.span()], value)),
+            };
+            next_discriminant = discriminant
+                .as_ref()
+                .and_then(|discriminant| discriminant.value.checked_add(1));
+            let mut named = self.derive_named(
+                inner,
+                &variant.ident,
+                &variant.original.attrs,
+                Some(container),
+            );
+            named.rust_attrs.discriminant = discriminant;
+            map.insert(idx as u32, named);
+        }
+        st::ContainerFormat::Enum(map)
+    }
+
+    pub(crate) fn derive_struct(
+        &mut self,
+        style: ast::Style,
+        fields: &[ast::Field<'a>],
+        container: &ast::Container,
+    ) -> st::ContainerFormat {
+        match style {
+            ast::Style::Struct => self.derive_struct_named_fields(fields, container),
+            ast::Style::Newtype => self.derive_struct_newtype(&fields[0], container),
+            ast::Style::Tuple => self.derive_struct_tuple(fields, container),
+            ast::Style::Unit => self.derive_struct_unit(),
+        }
+    }
+
+    fn derive_struct_newtype(
+        &mut self,
+        field: &ast::Field<'a>,
+        _container: &ast::Container,
+    ) -> st::ContainerFormat {
+        if field.attrs.skip_serializing() {
+            return self.derive_struct_unit();
+        }
+
+        st::ContainerFormat::NewTypeStruct(Box::new(self.field_to_format(field)))
+    }
+
+    fn derive_struct_unit(&self) -> st::ContainerFormat {
+        st::ContainerFormat::UnitStruct
+    }
+
+    fn derive_struct_named_fields(
+        &mut self,
+        fields: &[ast::Field<'a>],
+        _container: &ast::Container,
+    ) -> st::ContainerFormat {
+        let fields = filter_visible(fields);
+        st::ContainerFormat::Struct(self.derive_named_fields(&fields).collect())
+    }
+
+    fn derive_struct_tuple(
+        &mut self,
+        fields: &[ast::Field<'a>],
+        ast_container: &ast::Container,
+    ) -> st::ContainerFormat {
+        let fields = filter_visible(fields);
+        if fields.is_empty() {
+            return self.derive_struct_unit();
+        }
+
+        if fields.len() == 1 && ast_container.attrs.transparent() {
+            return self.derive_struct_newtype(fields[0], ast_container);
+        };
+
+        st::ContainerFormat::TupleStruct(self.derive_named_fields(&fields).collect())
+    }
+
+    /// Joins the lines of doc comments, removing their common indentation and surrounding blank lines.
+    /// Docs which aren't string literals, like `#[doc = include_str!("README.md")]`, are replaced by
+    /// placeholders to be filled in at runtime (see [utils::doc_include_placeholder]).
+    fn get_doc_comments(&mut self, attrs: &[syn::Attribute]) -> Option<String> {
+        // (line, is from a placeholder)
+        let mut lines = Vec::<(String, bool)>::new();
+        for attr in attrs {
+            if let ("doc", syn::Meta::NameValue(name_value)) =
+                (path_to_string(attr.path()).as_str(), &attr.meta)
+            {
+                if let syn::Expr::Lit(syn::ExprLit {
+                    attrs: _,
+                    lit: syn::Lit::Str(s),
+                }) = &name_value.value
+                {
+                    lines.extend(s.value().split('\n').map(|line| (line.to_string(), false)));
+                } else {
+                    lines.push((
+                        utils::doc_include_placeholder(self.doc_includes.len()),
+                        true,
+                    ));
+                    self.doc_includes.push(name_value.value.clone());
+                }
+            }
+        }
+
+        let indent = lines
+            .iter()
+            .filter(|(line, is_placeholder)| !is_placeholder && !line.trim().is_empty())
+            .map(|(line, _)| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let lines = lines
+            .iter()
+            .map(|(line, is_placeholder)| {
+                if *is_placeholder {
+                    line.as_str()
+                } else {
+                    line.get(indent..)
+                        .unwrap_or_else(|| line.trim_start())
+                        .trim_end()
+                }
+            })
+            .collect::<Vec<_>>();
+        let first = lines.iter().position(|line| !line.is_empty())?;
+        let last = lines.iter().rposition(|line| !line.is_empty())?;
+        Some(lines[first..=last].join("\n"))
+    }
+
+    fn derive_named<T>(
+        &mut self,
+        value: T,
+        ident: &syn::Ident,
+        syn_attrs: &[syn::Attribute],
+        container: Option<&ast::Container>,
+        // serde_name: &serde_derive_internals::attr::Name,
+    ) -> st::Named<T> {
+        self.derive_named_at(value, ident.to_string(), ident.span(), syn_attrs, container)
+    }
+
+    /// Like [ParseContext::derive_named], but for names which are not identifiers,
+    /// such as the positions of tuple members (`"0"`, `"1"`, ...).
+    fn derive_named_at<T>(
+        &mut self,
+        value: T,
+        ident_str: String,
+        ident_span: Span,
+        syn_attrs: &[syn::Attribute],
+        container: Option<&ast::Container>,
+    ) -> st::Named<T> {
+        let mut named = st::Named {
+            rust_ident: spanned(&[ident_span], ident_str),
+            rust_generics: container
+                .map(|c| type_generics(c.generics))
+                .unwrap_or_default(),
+            rust_docs: self.get_doc_comments(syn_attrs),
+            codegen_attrs: Vec::new(),
+            codegen_flags: Vec::new(),
+            codegen_scoped: Vec::new(),
+            serde_attrs: Vec::new(),
+            serde_flags: Vec::new(),
+            serde_meta: Vec::new(),
+            rust_attrs: Default::default(),
+            captured_default: None,
+            value,
+        };
+        // keys of scoped attributes for generations without targets, with the span of their scope
+        let mut scoped_keys = Vec::new();
+        for attr in syn_attrs.iter() {
+            if attr.path().is_ident("serde") {
+                // // #[repr(align(N))]
+                // attr.parse_nested_meta(|meta| {
+                //     let content;
+                //     syn::parenthesized!(content in meta.input);
+                //     let lit: syn::LitInt = content.parse()?;
+                //     let n: usize = lit.base10_parse()?;
+                //     repr_align = Some(n);
+                //     return Ok(());
+                // });
+                attr.parse_nested_meta(|meta| {
+                    let span = meta.path.span();
+                    let attr_meta = parse_attr_meta(meta)?;
+                    // keep the simple `key = "value"` and `flag` forms handy
+                    match attr_meta.value {
+                        st::AttrMetaValue::Lit(ref lit) => named
+                            .serde_attrs
+                            .push(spanned(&[span], (attr_meta.key.clone(), lit.clone()))),
+                        st::AttrMetaValue::Flag => named.serde_flags.push(attr_meta.key.clone()),
+                        st::AttrMetaValue::List(_) => {}
+                    }
+                    named.serde_meta.push(attr_meta);
+                    Ok(())
+                })
+                .expect("parsed serde attribute");
+            } else if is_codegen_attr(attr) {
+                let parsed = attr.parse_nested_meta(|meta| {
+                    if meta.input.peek(syn::token::Paren) {
+                        let scope_span = meta.path.span();
+                        for scoped in parse_codegen_scope(meta)? {
+                            if let Some(key) = flattened_scoped_key(&scoped.value) {
+                                scoped_keys.push((key, scope_span));
+                            }
+                            named.codegen_scoped.push(scoped);
+                        }
+                        return Ok(());
+                    }
+                    // e.g. `cfg = "feature = \"admin\""` for items whose own `#[cfg]` the compiler
+                    // has already evaluated and removed
+                    if meta.path.is_ident("cfg") {
+                        let lit: syn::LitStr = meta.value()?.parse()?;
+                        let predicate: syn::Meta = lit.parse()?;
+                        named
+                            .rust_attrs
+                            .cfg
+                            .push(spanned(&[lit.span()], meta_to_string(&predicate)));
+                        return Ok(());
+                    }
+                    let span = meta.input.span();
+                    match meta.value() {
+                        Ok(value) => {
+                            let lit: syn::LitStr = value.parse()?;
+                            named.codegen_attrs.push(spanned(
+                                &[span],
+                                (
+                                    spanned(&[meta.input.span()], path_to_string(&meta.path)),
+                                    spanned(&[lit.span()], lit.value()),
+                                ),
+                            ))
+                        }
+                        Err(_other) => {
+                            // not a value, assume it's a flag, then?
+                            named
+                                .codegen_flags
+                                .push(spanned(&[meta.input.span()], path_to_string(&meta.path)))
+                        }
+                    }
+                    Ok(())
+                });
+                if let Err(err) = parsed {
+                    self.errors.push(err);
+                }
+            } else if attr.path().is_ident("repr") {
+                // #[repr(C, u8)] or #[repr(align(8))]
+                let reprs = attr
+                    .parse_args_with(
+                        syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+                    )
+                    .expect("parsed repr attribute");
+                for repr in reprs {
+                    let repr_str = quote!(#repr).to_string().replace(' ', "");
+                    named
+                        .rust_attrs
+                        .repr
+                        .push(spanned(&[repr.span()], repr_str));
+                }
+            } else if attr.path().is_ident("derive") {
+                let derives = attr
+                    .parse_args_with(
+                        syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated,
+                    )
+                    .expect("parsed derive attribute");
+                for derive in derives {
+                    if let Some(last) = derive.segments.last() {
+                        named
+                            .rust_attrs
+                            .derives
+                            .push(spanned(&[last.ident.span()], last.ident.to_string()));
+                    }
+                }
+            } else if attr.path().is_ident("deprecated") {
+                let mut deprecated = st::Deprecated::default();
+                match &attr.meta {
+                    // #[deprecated]
+                    syn::Meta::Path(_) => {}
+                    // #[deprecated = "note"]
+                    syn::Meta::NameValue(name_value) => {
+                        if let syn::Expr::Lit(syn::ExprLit {
+                            lit: syn::Lit::Str(lit),
+                            ..
+                        }) = &name_value.value
+                        {
+                            deprecated.note = Some(spanned(&[lit.span()], lit.value()));
+                        }
+                    }
+                    // #[deprecated(since = "1.2.0", note = "note")]
+                    syn::Meta::List(_) => attr
+                        .parse_nested_meta(|meta| {
+                            let lit: syn::LitStr = meta.value()?.parse()?;
+                            let value = Some(spanned(&[lit.span()], lit.value()));
+                            if meta.path.is_ident("since") {
+                                deprecated.since = value;
+                            } else if meta.path.is_ident("note") {
+                                deprecated.note = value;
+                            }
+                            Ok(())
+                        })
+                        .expect("parsed deprecated attribute"),
+                }
+                named.rust_attrs.deprecated = Some(spanned(&[attr.path().span()], deprecated));
+            } else if attr.path().is_ident("non_exhaustive") {
+                named.rust_attrs.non_exhaustive = Some(spanned(&[attr.path().span()], ()));
+            } else if attr.path().is_ident("must_use") {
+                let reason = match &attr.meta {
+                    syn::Meta::NameValue(syn::MetaNameValue {
+                        value:
+                            syn::Expr::Lit(syn::ExprLit {
+                                lit: syn::Lit::Str(lit),
+                                ..
+                            }),
+                        ..
+                    }) => Some(lit.value()),
+                    _ => None,
+                };
+                named.rust_attrs.must_use = Some(spanned(&[attr.path().span()], reason));
+            } else if attr.path().is_ident("cfg") {
+                let predicate: syn::Meta = attr.parse_args().expect("parsed cfg attribute");
+                named
+                    .rust_attrs
+                    .cfg
+                    .push(spanned(&[predicate.span()], meta_to_string(&predicate)));
+            } else if attr.path().is_ident("cfg_attr") {
+                let mut metas = attr
+                    .parse_args_with(
+                        syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+                    )
+                    .expect("parsed cfg_attr attribute")
+                    .into_iter();
+                if let Some(predicate) = metas.next() {
+                    named.rust_attrs.cfg_attr.push(spanned(
+                        &[predicate.span()],
+                        (
+                            meta_to_string(&predicate),
+                            metas.map(|meta| meta_to_string(&meta)).collect(),
+                        ),
+                    ));
+                }
+            } else if attr.path().is_ident("doc") {
+                // #[doc(hidden)], while doc comments are collected by [get_doc_comments]
+                if let syn::Meta::List(list) = &attr.meta {
+                    let metas = list
+                        .parse_args_with(
+                            syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+                        )
+                        .expect("parsed doc attribute");
+                    for meta in metas {
+                        if meta.path().is_ident("hidden") {
+                            named.rust_attrs.doc_hidden = Some(spanned(&[meta.span()], ()));
+                        }
+                    }
+                }
+            }
+        }
+        // e.g. `ts(as = "Date")` next to `ts_as = "number"`, which would silently override it
+        for (flattened, span) in scoped_keys {
+            let clashes = named
+                .codegen_attrs
+                .iter()
+                .map(|attr| &attr.value.0.value)
+                .chain(named.codegen_flags.iter().map(|flag| &flag.value))
+                .any(|key| *key == flattened);
+            if clashes {
+                self.errors.push(syn::Error::new(
+                    span,
+                    format!("`{flattened}` is given both in this scope and directly, only one of them can be used"),
+                ));
+            }
+        }
+        named
+    }
+}
+
+/// The key which a target scoped attribute like `ts(as = "Date")` has for generations without
+/// targets, e.g. `ts_as`. `None` for attributes which are only scoped by tags.
+fn flattened_scoped_key(scoped: &st::ScopedCodegenAttr) -> Option<String> {
+    let mut parts = scoped
+        .scopes
+        .iter()
+        .filter_map(|scope| match scope {
+            st::CodegenScope::Target(target) => Some(target.as_str()),
+            st::CodegenScope::Tags(_) => None,
+        })
+        .collect::<Vec<_>>();
+    if parts.is_empty() {
+        return None;
+    }
+    parts.push(&scoped.key.value);
+    Some(parts.join("_"))
+}
+
+/// e.g. `#[codegen(tags = "a")]` or `#[i_codegen_derive::codegen(tags = "a")]`
+fn is_codegen_attr(attr: &syn::Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "codegen")
+}
+
+/// The attributes of an item, starting with the arguments of an attribute macro like
+/// `#[fn_codegen(tags = "a")]` as `#[codegen(tags = "a")]`.
+fn attrs_with_codegen_args(
+    attribute: TokenStream,
+    item_attrs: &[syn::Attribute],
+) -> Vec<syn::Attribute> {
+    let mut attrs = Vec::new();
+    if !attribute.is_empty() {
+        attrs.push(syn::parse_quote!(#[codegen(#attribute)]));
+    }
+    attrs.extend(item_attrs.iter().cloned());
+    attrs
+}
+
+/// Like [attrs_with_codegen_args] for the `#[codegen]` attribute macro itself. Any further
+/// `#[codegen(...)]` attributes are removed from the item, rather than each registering it again.
+fn take_codegen_attrs(
+    attribute: TokenStream,
+    item_attrs: &mut Vec<syn::Attribute>,
+) -> Vec<syn::Attribute> {
+    let attrs = attrs_with_codegen_args(attribute, item_attrs);
+    item_attrs.retain(|attr| !is_codegen_attr(attr));
+    attrs
+}
+
+/// The names of type parameters, leaving out lifetimes and const generics.
+fn type_generics(generics: &syn::Generics) -> Vec<st::Spanned<String>> {
+    generics
+        .type_params()
+        .map(|typ| spanned(&[typ.ident.span()], typ.ident.to_string()))
+        .collect()
+}
+
+/// Names of [type_generics], for [ParseContext::generic_params]
+fn type_generic_names(generics: &syn::Generics) -> Vec<String> {
+    generics
+        .type_params()
+        .map(|typ| typ.ident.to_string())
+        .collect()
+}
+
+/// e.g. `"Into<String>"` or `"Vec<T>: Debug"`, without the spaces `quote` puts between all tokens
+fn tokens_to_compact_string(tokens: impl quote::ToTokens) -> String {
+    let spaced = tokens.to_token_stream().to_string();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let chars = spaced.chars().collect::<Vec<_>>();
+    let at = |idx: usize, offset: isize| {
+        idx.checked_add_signed(offset)
+            .and_then(|idx| chars.get(idx))
+            .copied()
+            .unwrap_or(' ')
+    };
+    chars
+        .iter()
+        .enumerate()
+        .filter(|(idx, c)| {
+            let (prev2, prev, next, next2) = (at(*idx, -2), at(*idx, -1), at(*idx, 1), at(*idx, 2));
+            **c != ' '
+                // between words, like `dyn Trait` or `&'a str`
+                || (is_word(prev) && is_word(next))
+                // after a single `:` of a bound, but not within `::`
+                || (prev == ':' && prev2 != ':' && next != ':')
+                || prev == ','
+                || matches!(prev, '+' | '=')
+                || matches!(next, '+' | '=')
+                || (next == '-' && next2 == '>')
+                || (prev == '>' && prev2 == '-')
+        })
+        .map(|(_, c)| *c)
+        .collect()
+}
+
+/// Whether there is a `#[codegen(flag)]`, for flags which change how the rest of the item is parsed.
+fn has_codegen_flag(attrs: &[syn::Attribute], flag: &str) -> bool {
+    let mut found = false;
+    for attr in attrs {
+        if is_codegen_attr(attr) {
+            // errors are reported when the attributes are parsed by [ParseContext::derive_named]
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.input.peek(syn::token::Paren) {
+                    // scoped items like `ts(...)` only apply to some generations
+                    let _scope;
+                    syn::parenthesized!(_scope in meta.input);
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::LitStr>()?;
+                } else if meta.path.is_ident(flag) {
+                    found = true;
+                }
+                Ok(())
+            });
+        }
+    }
+    found
+}
+
+/// e.g. `T` for `impl Future<Output = T>`, `Pin<Box<dyn Future<Output = T> + Send>>`,
+/// `BoxFuture<'static, T>` or `oneshot::Receiver<T>`
+fn future_output<'a>(ty: &'a syn::Type, generics: &'a syn::Generics) -> Option<&'a syn::Type> {
+    wrapped_type(
+        ty,
+        generics,
+        ("Future", "Output"),
+        &["BoxFuture", "LocalBoxFuture", "oneshot::Receiver"],
+    )
+}
+
+/// e.g. `T` for `impl Stream<Item = T>`, `BoxStream<'static, T>` or `mpsc::Receiver<T>`,
+/// or for `S` with `S: Stream<Item = T>`. Channel receivers must be written with their module,
+/// so that a type of your own like `Receiver<T>` isn't taken for a stream.
+fn stream_item<'a>(ty: &'a syn::Type, generics: &'a syn::Generics) -> Option<&'a syn::Type> {
+    if future_output(ty, generics).is_some() {
+        return None;
+    }
+    wrapped_type(
+        ty,
+        generics,
+        ("Stream", "Item"),
+        &[
+            "BoxStream",
+            "LocalBoxStream",
+            "mpsc::Receiver",
+            "mpsc::UnboundedReceiver",
+            "broadcast::Receiver",
+            "ReceiverStream",
+            "UnboundedReceiverStream",
+        ],
+    )
+}
+
+/// The associated type of a trait like `(Future, Output)` for an `impl Trait`, `dyn Trait` or
+/// a type parameter bounded by the trait, through `Pin`, `Box` and references.
+/// Otherwise, the first generic argument of one of the `wrappers`, matched by their last path segments.
+fn wrapped_type<'a>(
+    ty: &'a syn::Type,
+    generics: &'a syn::Generics,
+    (trait_ident, assoc_ident): (&str, &str),
+    wrappers: &[&str],
+) -> Option<&'a syn::Type> {
+    let from_bound = |bound: &'a syn::TypeParamBound| match bound {
+        syn::TypeParamBound::Trait(bound) => {
+            let last = bound.path.segments.last()?;
+            if last.ident != trait_ident {
+                return None;
+            }
+            match &last.arguments {
+                syn::PathArguments::AngleBracketed(args) => {
+                    args.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::AssocType(assoc) if assoc.ident == assoc_ident => {
+                            Some(&assoc.ty)
+                        }
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let recurse = |ty| wrapped_type(ty, generics, (trait_ident, assoc_ident), wrappers);
+    match ty {
+        syn::Type::Paren(syn::TypeParen { elem, .. })
+        | syn::Type::Group(syn::TypeGroup { elem, .. })
+        | syn::Type::Reference(syn::TypeReference { elem, .. }) => recurse(elem),
+        syn::Type::ImplTrait(syn::TypeImplTrait { bounds, .. })
+        | syn::Type::TraitObject(syn::TypeTraitObject { bounds, .. }) => {
+            bounds.iter().find_map(from_bound)
+        }
+        syn::Type::Path(syn::TypePath { qself: None, path }) => {
+            if let Some(ident) = path.get_ident() {
+                // e.g. `S` with `S: Stream<Item = T>` or `where S: Stream<Item = T>`
+                let param_bounds = generics
+                    .type_params()
+                    .filter(|param| param.ident == *ident)
+                    .flat_map(|param| &param.bounds);
+                let where_bounds = generics
+                    .where_clause
+                    .iter()
+                    .flat_map(|where_clause| &where_clause.predicates)
+                    .filter_map(|predicate| match predicate {
+                        syn::WherePredicate::Type(predicate) => match &predicate.bounded_ty {
+                            syn::Type::Path(bounded) if bounded.path.is_ident(ident) => {
+                                Some(&predicate.bounds)
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    })
+                    .flatten();
+                if let Some(found) = param_bounds.chain(where_bounds).find_map(from_bound) {
+                    return Some(found);
+                }
+            }
+            let last = path.segments.last()?;
+            let first_type_arg = match &last.arguments {
+                syn::PathArguments::AngleBracketed(args) => {
+                    args.args.iter().find_map(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }?;
+            let is_wrapper = wrappers.iter().any(|wrapper| {
+                let wrapper_idents = wrapper.split("::").collect::<Vec<_>>();
+                path.segments.len() >= wrapper_idents.len()
+                    && path
+                        .segments
+                        .iter()
+                        .rev()
+                        .zip(wrapper_idents.iter().rev())
+                        .all(|(segment, ident)| segment.ident == ident)
+            });
+            if is_wrapper {
+                Some(first_type_arg)
+            } else if last.ident == "Pin" || last.ident == "Box" {
+                recurse(first_type_arg)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Every value of `key` in `#[codegen(key = "value")]`s, for keys which change how the rest of the item is parsed.
+fn codegen_attr_values(attrs: &[syn::Attribute], key: &str) -> Vec<syn::LitStr> {
+    let mut values = Vec::new();
+    for attr in attrs {
+        if is_codegen_attr(attr) {
+            // other errors are reported when the attributes are parsed by [ParseContext::derive_named]
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.input.peek(syn::token::Paren) {
+                    // scoped items like `ts(...)` only apply to some generations
+                    let _scope;
+                    syn::parenthesized!(_scope in meta.input);
+                } else if meta.input.peek(syn::Token![=]) {
+                    let value = meta.value()?.parse::<syn::LitStr>()?;
+                    if meta.path.is_ident(key) {
+                        values.push(value);
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+    values
+}
+
+const HTTP_METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS", "TRACE",
+];
+
+/// Whether the attribute may be a route of actix-web or rocket, like `#[get(...)]`, `#[actix_web::get(...)]`
+/// or `#[rocket::get(...)]`, rather than another attribute which is named like an HTTP method.
+fn is_framework_route_path(path: &syn::Path) -> bool {
+    match path.segments.len() {
+        1 => true,
+        2 => ["actix_web", "rocket"]
+            .iter()
+            .any(|framework| path.segments[0].ident == framework),
+        _ => false,
+    }
+}
+
+/// The route from `#[fn_codegen(method = "POST", path = "/users/{id}")]` or a framework
+/// attribute like `#[post("/users/{id}")]`, with its placeholders checked against the
+/// `Path` extractors of the function, or against its parameter names if it has none.
+/// Framework attributes above `#[fn_codegen]` have already been expanded, so they aren't in `attrs`.
+fn function_route(
+    attrs: &[syn::Attribute],
+    sig: &syn::Signature,
+    fn_format: &st::FunctionFormat,
+) -> Result<Option<st::Route>> {
+    let method = codegen_attr_values(attrs, "method").pop();
+    let path = codegen_attr_values(attrs, "path").pop();
+    let (method, path) = match (method, path) {
+        (Some(method), Some(path)) => ((method.value().to_uppercase(), method.span()), path),
+        (Some(lit), None) | (None, Some(lit)) => {
+            return Err(syn::Error::new(
+                lit.span(),
+                "`method` and `path` must be given together",
+            ))
+        }
+        (None, None) => {
+            // e.g. `#[get("/users/{id}")]` of actix-web or rocket
+            let framework_route = attrs.iter().find_map(|attr| {
+                if !is_framework_route_path(attr.path()) {
+                    return None;
+                }
+                let ident = &attr.path().segments.last()?.ident;
+                let method = ident.to_string().to_uppercase();
+                if !HTTP_METHODS.contains(&method.as_str()) {
+                    return None;
+                }
+                let path = attr
+                    .parse_args_with(|input: syn::parse::ParseStream| {
+                        let path = input.parse::<syn::LitStr>()?;
+                        input.parse::<TokenStream>()?;
+                        Ok(path)
+                    })
+                    .ok()?;
+                Some(((method, ident.span()), path))
+            });
+            match framework_route {
+                Some(route) => route,
+                None => return Ok(None),
+            }
+        }
+    };
+    let (method, method_span) = method;
+    if !HTTP_METHODS.contains(&method.as_str()) {
+        return Err(syn::Error::new(
+            method_span,
+            format!(
+                "unknown HTTP method, expected one of {}",
+                HTTP_METHODS.join(", ")
+            ),
+        ));
+    }
+
+    // e.g. `/users/{id}`, `/users/:id` or `/users/<id>`, with wildcards like `{*rest}` or `<rest..>`
+    let mut segments = Vec::new();
+    let mut placeholders = Vec::new();
+    for segment in path.value().split('/') {
+        let name = segment
+            .strip_prefix('{')
+            .and_then(|name| name.strip_suffix('}'))
+            .map(|name| name.trim_start_matches('*'))
+            .or_else(|| {
+                segment
+                    .strip_prefix('<')
+                    .and_then(|name| name.strip_suffix('>'))
+                    .map(|name| name.trim_end_matches(".."))
+            })
+            .or_else(|| segment.strip_prefix(':'))
+            .or_else(|| segment.strip_prefix('*'));
+        match name {
+            Some(name) if syn::parse_str::<Ident>(name).is_ok() => {
+                segments.push(format!("{{{name}}}"));
+                placeholders.push(name.to_string());
+            }
+            _ if segment.contains(['{', '}', '<', '>']) => {
+                return Err(syn::Error::new(
+                    path.span(),
+                    format!(
+                    "expected a placeholder like `{{id}}` for the whole path segment `{segment}`"
+                ),
+                ))
+            }
+            _ => segments.push(segment.to_string()),
+        }
+    }
+
+    // the bindings of `Path` extractors, e.g. `org` and `team` for `Path((org, team)): Path<(u64, u64)>`
+    let typed_params = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat) => Some(pat),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let mut bindings = Vec::new();
+    for extractor in &fn_format.extractors {
+        if !matches!(extractor.source, st::ParamSource::Path) {
+            continue;
+        }
+        match &*typed_params[extractor.param].pat {
+            syn::Pat::TupleStruct(tuple) if tuple.elems.len() == 1 => match &tuple.elems[0] {
+                syn::Pat::Tuple(fields) => {
+                    bindings.extend(fields.elems.iter().enumerate().map(|(field, pat)| {
+                        let binding = match pat {
+                            syn::Pat::Ident(ident) => Some(ident.ident.to_string()),
+                            _ => None,
+                        };
+                        (extractor.param, Some(field), binding)
+                    }))
+                }
+                _ => bindings.push((extractor.param, None, None)),
+            },
+            _ => bindings.push((extractor.param, None, None)),
+        }
+    }
+    let path_params = if bindings.is_empty() && fn_format.extractors.is_empty() {
+        // plain functions, e.g. `fn get_user(id: u64)` for `/users/{id}`
+        placeholders
+            .iter()
+            .map(|name| {
+                let param = fn_format
+                    .params
+                    .iter()
+                    .position(|param| param.rust_ident.value == *name)
+                    .ok_or_else(|| {
+                        syn::Error::new(
+                            path.span(),
+                            format!("`{{{name}}}` in the path is not a parameter of the function"),
+                        )
+                    })?;
+                Ok(st::PathParam {
+                    name: name.clone(),
+                    param,
+                    field: None,
+                    binding: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+    } else if let [(param, None, _)] = bindings.as_slice() {
+        // a single value or a struct like `Path(params): Path<Params>`, for every placeholder
+        if placeholders.is_empty() {
+            return Err(syn::Error::new(
+                path.span(),
+                "the path has no placeholders for the `Path` extractor",
+            ));
+        }
+        placeholders
+            .iter()
+            .map(|name| st::PathParam {
+                name: name.clone(),
+                param: *param,
+                field: None,
+                binding: None,
+            })
+            .collect()
+    } else if bindings.len() == placeholders.len() {
+        placeholders
+            .iter()
+            .zip(bindings)
+            .map(|(name, (param, field, binding))| st::PathParam {
+                name: name.clone(),
+                param,
+                field,
+                binding,
+            })
+            .collect()
+    } else {
+        return Err(syn::Error::new(
+            path.span(),
+            format!(
+                "the path has {} placeholders, but the `Path` extractors have {} values",
+                placeholders.len(),
+                bindings.len()
+            ),
+        ));
+    };
+
+    Ok(Some(st::Route {
+        method: spanned(&[method_span], method),
+        path: spanned(&[path.span()], segments.join("/")),
+        path_params,
+    }))
+}
+
+/// Paths from `#[codegen(result_alias = "...")]`, see [ParseContext::result_aliases]
+fn codegen_result_aliases(attrs: &[syn::Attribute]) -> Result<Vec<syn::Path>> {
+    codegen_attr_values(attrs, "result_alias")
+        .iter()
+        .map(|alias| alias.parse::<syn::Path>())
+        .collect()
+}
 
-    for field in fields {
-        if field.attrs.skip_serializing() || is_phantom(field.ty) {
-            continue;
+/// Extractors of web frameworks by the last segment of their path, for `#[codegen(extractors = "axum")]`.
+/// These are only recognized with a type argument, like `Json<CreateUser>`, see [ParseContext::frameworks].
+const FRAMEWORK_EXTRACTORS: &[(&str, &[(&str, st::ParamSource)])] = &[
+    (
+        "axum",
+        &[
+            ("Json", st::ParamSource::Body),
+            ("Form", st::ParamSource::Body),
+            ("Path", st::ParamSource::Path),
+            ("Query", st::ParamSource::Query),
+            ("TypedHeader", st::ParamSource::Header),
+            ("State", st::ParamSource::State),
+            ("Extension", st::ParamSource::State),
+        ],
+    ),
+    (
+        "actix",
+        &[
+            ("Json", st::ParamSource::Body),
+            ("Form", st::ParamSource::Body),
+            ("Path", st::ParamSource::Path),
+            ("Query", st::ParamSource::Query),
+            ("Header", st::ParamSource::Header),
+            ("Data", st::ParamSource::State),
+        ],
+    ),
+];
+
+/// From `#[codegen(extractors = "axum")]`, see [ParseContext::frameworks]
+fn codegen_frameworks(
+    attrs: &[syn::Attribute],
+) -> Result<Vec<&'static [(&'static str, st::ParamSource)]>> {
+    let mut frameworks = Vec::new();
+    for lit in codegen_attr_values(attrs, "extractors") {
+        for name in lit.value().split(',').map(str::trim) {
+            let (_, extractors) = FRAMEWORK_EXTRACTORS
+                .iter()
+                .find(|(framework, _)| *framework == name)
+                .ok_or_else(|| {
+                    syn::Error::new(
+                        lit.span(),
+                        format!(
+                            "unknown framework `{name}`, expected one of {}",
+                            FRAMEWORK_EXTRACTORS
+                                .iter()
+                                .map(|(framework, _)| format!("`{framework}`"))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    )
+                })?;
+            frameworks.push(*extractors);
         }
-
-        content.push(field);
     }
-    content
+    Ok(frameworks)
 }
 
-impl<'a> ParseContext {
-    pub(crate) fn derive_enum(
-        &mut self,
-        variants: &[ast::Variant<'a>],
-        container: &ast::Container,
-    ) -> st::ContainerFormat {
-        let mut map = BTreeMap::<u32, st::Named<st::VariantFormat>>::new();
-        for (idx, variant) in variants.iter().enumerate() {
-            let inner: st::VariantFormat = match variant.style {
-                ast::Style::Struct => st::VariantFormat::Struct(
-                    self.derive_named_fields_alt(&variant.fields).collect(),
-                ),
-                ast::Style::Tuple => st::VariantFormat::Tuple(
-                    self.derive_fields_tuple_alt(&variant.fields).collect(),
-                ),
-                ast::Style::Newtype => {
-                    st::VariantFormat::NewType(Box::new(self.field_to_format(&variant.fields[0])))
+/// From `#[codegen(extractor = "Cbor = body")]`, see [ParseContext::extractors]
+fn codegen_extractors(attrs: &[syn::Attribute]) -> Result<Vec<(String, st::ParamSource)>> {
+    codegen_attr_values(attrs, "extractor")
+        .iter()
+        .map(|lit| {
+            let value = lit.value();
+            let (ident, source) = value.split_once('=').ok_or_else(|| {
+                syn::Error::new(lit.span(), "expected an extractor like `Cbor = body`")
+            })?;
+            let source = match source.trim() {
+                "body" => st::ParamSource::Body,
+                "path" => st::ParamSource::Path,
+                "query" => st::ParamSource::Query,
+                "header" => st::ParamSource::Header,
+                "state" => st::ParamSource::State,
+                other => {
+                    return Err(syn::Error::new(
+                        lit.span(),
+                        format!(
+                            "unknown source `{other}`, expected one of `body`, `path`, `query`, `header` or `state`"
+                        ),
+                    ))
                 }
-                ast::Style::Unit => st::VariantFormat::Unit,
             };
-            map.insert(
-                idx as u32,
-                self.derive_named(
-                    inner,
-                    &variant.ident,
-                    &variant.original.attrs,
-                    Some(container),
-                ),
-            );
-        }
-        st::ContainerFormat::Enum(map)
-    }
+            Ok((ident.trim().to_string(), source))
+        })
+        .collect()
+}
 
-    pub(crate) fn derive_struct(
-        &mut self,
-        style: ast::Style,
-        fields: &[ast::Field<'a>],
-        container: &ast::Container,
-    ) -> st::ContainerFormat {
-        match style {
-            ast::Style::Struct => self.derive_struct_named_fields(fields, container),
-            ast::Style::Newtype => self.derive_struct_newtype(&fields[0], container),
-            ast::Style::Tuple => self.derive_struct_tuple(fields, container),
-            ast::Style::Unit => self.derive_struct_unit(),
+/// The items of a scope in `#[codegen(...)]` like `ts(as = "Date")`, or `when(tag = "a", rename = "b")`
+/// whose `tag`s are its condition. Scopes can be nested.
+fn parse_codegen_scope(
+    meta: syn::meta::ParseNestedMeta,
+) -> Result<Vec<st::Spanned<st::ScopedCodegenAttr>>> {
+    let is_when = meta.path.is_ident("when");
+    let mut tags = Vec::new();
+    let mut attrs = Vec::new();
+    meta.parse_nested_meta(|nested| {
+        let span = nested.path.span();
+        let key = spanned(&[span], path_to_string(&nested.path));
+        if nested.input.peek(syn::token::Paren) {
+            attrs.extend(parse_codegen_scope(nested)?);
+        } else if nested.input.peek(syn::Token![=]) {
+            let lit: syn::LitStr = nested.value()?.parse()?;
+            if is_when && (key.value == "tag" || key.value == "tags") {
+                tags.extend(
+                    lit.value()
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(String::from),
+                );
+            } else {
+                let value = Some(spanned(&[lit.span()], lit.value()));
+                attrs.push(spanned(
+                    &[span],
+                    st::ScopedCodegenAttr {
+                        scopes: Vec::new(),
+                        key,
+                        value,
+                    },
+                ));
+            }
+        } else {
+            attrs.push(spanned(
+                &[span],
+                st::ScopedCodegenAttr {
+                    scopes: Vec::new(),
+                    key,
+                    value: None,
+                },
+            ));
         }
-    }
-
-    fn derive_struct_newtype(
-        &mut self,
-        field: &ast::Field<'a>,
-        _container: &ast::Container,
-    ) -> st::ContainerFormat {
-        if field.attrs.skip_serializing() {
-            return self.derive_struct_unit();
+        Ok(())
+    })?;
+    let scope = if is_when {
+        if tags.is_empty() {
+            return Err(
+                meta.error("expected `when(tag = \"...\", ...)` with the tags it applies to")
+            );
         }
-
-        st::ContainerFormat::NewTypeStruct(Box::new(self.field_to_format(field)))
+        st::CodegenScope::Tags(tags)
+    } else {
+        st::CodegenScope::Target(path_to_string(&meta.path))
+    };
+    for attr in &mut attrs {
+        attr.value.scopes.insert(0, scope.clone());
     }
+    Ok(attrs)
+}
 
-    fn derive_struct_unit(&self) -> st::ContainerFormat {
-        st::ContainerFormat::UnitStruct
+/// Parses any item of an attribute like `#[serde(...)]`, such as `flatten`, `rename = "a"`,
+/// `skip_serializing_if = "Option::is_none"` or `bound(serialize = "T: Serialize")`.
+fn parse_attr_meta(meta: syn::meta::ParseNestedMeta) -> Result<st::AttrMeta> {
+    let key = spanned(&[meta.path.span()], path_to_compact_string(&meta.path));
+    let value = if meta.input.peek(syn::Token![=]) {
+        let value: syn::Expr = meta.value()?.parse()?;
+        let lit = match value {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(ref lit),
+                ..
+            }) => lit.value(),
+            ref other => quote!(#other).to_string(),
+        };
+        st::AttrMetaValue::Lit(spanned(&[value.span()], lit))
+    } else if meta.input.peek(syn::token::Paren) {
+        let mut list = Vec::new();
+        meta.parse_nested_meta(|nested| {
+            list.push(parse_attr_meta(nested)?);
+            Ok(())
+        })?;
+        st::AttrMetaValue::List(list)
+    } else {
+        st::AttrMetaValue::Flag
+    };
+    Ok(st::AttrMeta { key, value })
+}
+
+/// e.g. `"pub"`, `"pub(crate)"`, `"pub(in crate::api)"`, or `""` for private items,
+/// which are located at their identifier.
+fn visibility_to_string(vis: &syn::Visibility, ident_span: Span) -> st::Spanned<String> {
+    match vis {
+        syn::Visibility::Public(public) => spanned(&[public.span], "pub".to_string()),
+        syn::Visibility::Restricted(restricted) => spanned(
+            &[restricted.span()],
+            format!(
+                "pub({}{})",
+                if restricted.in_token.is_some() {
+                    "in "
+                } else {
+                    ""
+                },
+                path_to_compact_string(&restricted.path)
+            ),
+        ),
+        syn::Visibility::Inherited => spanned(&[ident_span], String::new()),
     }
+}
 
-    fn derive_struct_named_fields(
-        &mut self,
-        fields: &[ast::Field<'a>],
-        _container: &ast::Container,
-    ) -> st::ContainerFormat {
-        let fields = filter_visible(fields);
-        st::ContainerFormat::Struct(self.derive_named_fields(&fields).collect())
+/// Like [path_to_string], but without spaces around `::`
+fn path_to_compact_string(path: &syn::Path) -> String {
+    let segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+    if path.leading_colon.is_some() {
+        format!("::{segments}")
+    } else {
+        segments
     }
+}
 
-    fn derive_struct_tuple(
-        &mut self,
-        fields: &[ast::Field<'a>],
-        ast_container: &ast::Container,
-    ) -> st::ContainerFormat {
-        let fields = filter_visible(fields);
-        if fields.is_empty() {
-            return self.derive_struct_unit();
+/// e.g. `all(unix, feature = "admin")`
+fn meta_to_string(meta: &syn::Meta) -> String {
+    match meta {
+        syn::Meta::Path(path) => path_to_compact_string(path),
+        syn::Meta::NameValue(name_value) => {
+            let value = &name_value.value;
+            format!(
+                "{} = {}",
+                path_to_compact_string(&name_value.path),
+                quote!(#value)
+            )
+        }
+        syn::Meta::List(list) => {
+            let inner = match list.parse_args_with(
+                syn::punctuated::Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated,
+            ) {
+                Ok(metas) => metas
+                    .iter()
+                    .map(meta_to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                Err(_) => list.tokens.to_string(),
+            };
+            format!("{}({})", path_to_compact_string(&list.path), inner)
         }
-
-        if fields.len() == 1 && ast_container.attrs.transparent() {
-            return self.derive_struct_newtype(&fields[0], ast_container);
-        };
-
-        st::ContainerFormat::TupleStruct(self.derive_fields_tuple(&fields).collect())
     }
+}
 
-    fn derive_named<T>(
-        &self,
-        value: T,
-        ident: &syn::Ident,
-        syn_attrs: &[syn::Attribute],
-        container: Option<&ast::Container>,
-        // serde_name: &serde_derive_internals::attr::Name,
-    ) -> st::Named<T> {
-        let ident_str = ident.to_string();
-        let mut named = st::Named {
-            rust_ident: spanned(&[ident.span()], ident_str),
-            rust_generics: container
-                .map(|c| {
-                    c.generics
-                        .params
-                        .iter()
-                        .filter_map(|g| match g {
-                            syn::GenericParam::Lifetime(_) => None,
-                            syn::GenericParam::Const(_) => None,
-                            syn::GenericParam::Type(typ) => {
-                                Some(spanned(&[typ.ident.span()], typ.ident.to_string()))
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .unwrap_or_default(),
-            rust_docs: get_doc_comments(syn_attrs),
-            codegen_attrs: Vec::new(),
-            codegen_flags: Vec::new(),
-            serde_attrs: Vec::new(),
-            serde_flags: Vec::new(),
-            value,
-        };
-        for attr in syn_attrs.iter() {
-            if attr.path().is_ident("serde") {
-                // // #[repr(align(N))]
-                // attr.parse_nested_meta(|meta| {
-                //     let content;
-                //     syn::parenthesized!(content in meta.input);
-                //     let lit: syn::LitInt = content.parse()?;
-                //     let n: usize = lit.base10_parse()?;
-                //     repr_align = Some(n);
-                //     return Ok(());
-                // });
-                attr.parse_nested_meta(|meta| {
-                    let span = meta.input.span();
-                    match meta.value() {
-                        Ok(value) => {
-                            let lit: syn::LitStr = value.parse()?;
-                            named.serde_attrs.push(spanned(
-                                &[span],
-                                (
-                                    spanned(&[meta.input.span()], path_to_string(&meta.path)),
-                                    spanned(&[lit.span()], lit.value()),
-                                ),
-                            ))
-                        }
-                        Err(_other) => {
-                            // not a value, assume it's a flag, then?
-                            named
-                                .serde_flags
-                                .push(spanned(&[meta.input.span()], path_to_string(&meta.path)))
-                        }
-                    }
-                    // // #[serde(rename = "new_name")]
-                    // if meta.path.is_ident("rename") {
-                    //     let value = meta.value()?;
-                    //     let lit: syn::LitStr = value.parse()?;
-                    //     named.rename = Some(spanned(lit.value(), &[lit.span(), span]))
-                    // }
-                    // // #[serde(flatten)]
-                    // else if meta.path.is_ident("flatten") {
-                    //     named.flatten = Some(spanned(true, &[span]))
-                    // } else {
-                    // }
-                    return Ok(());
-                })
-                .expect("parsed serde attribute");
-            } else if attr.path().is_ident("codegen")
-                || attr
-                    .path()
-                    .segments
-                    .last()
-                    .map(|a| a.ident.to_string() == "codegen")
-                    .unwrap_or(false)
-            {
-                attr.parse_nested_meta(|meta| {
-                    let span = meta.input.span();
-                    match meta.value() {
-                        Ok(value) => {
-                            let lit: syn::LitStr = value.parse()?;
-                            named.codegen_attrs.push(spanned(
-                                &[span],
-                                (
-                                    spanned(&[meta.input.span()], path_to_string(&meta.path)),
-                                    spanned(&[lit.span()], lit.value()),
-                                ),
-                            ))
-                        }
-                        Err(_other) => {
-                            // not a value, assume it's a flag, then?
-                            named
-                                .codegen_flags
-                                .push(spanned(&[meta.input.span()], path_to_string(&meta.path)))
-                        }
-                    }
-                    return Ok(());
-                })
-                .expect("parsed codegen attribute");
+/// Evaluates enum discriminants written as integer expressions, like `1`, `-1` or `1 << 4`.
+/// Returns `None` for anything which needs more information, such as constants.
+fn eval_discriminant(expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int.base10_parse().ok(),
+        syn::Expr::Paren(syn::ExprParen { expr, .. })
+        | syn::Expr::Group(syn::ExprGroup { expr, .. }) => eval_discriminant(expr),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => eval_discriminant(expr)?.checked_neg(),
+        syn::Expr::Binary(syn::ExprBinary {
+            left, op, right, ..
+        }) => {
+            let left = eval_discriminant(left)?;
+            let right = eval_discriminant(right)?;
+            match op {
+                syn::BinOp::Add(_) => left.checked_add(right),
+                syn::BinOp::Sub(_) => left.checked_sub(right),
+                syn::BinOp::Mul(_) => left.checked_mul(right),
+                syn::BinOp::Div(_) => left.checked_div(right),
+                syn::BinOp::Rem(_) => left.checked_rem(right),
+                syn::BinOp::Shl(_) => left.checked_shl(u32::try_from(right).ok()?),
+                syn::BinOp::Shr(_) => left.checked_shr(u32::try_from(right).ok()?),
+                syn::BinOp::BitOr(_) => Some(left | right),
+                syn::BinOp::BitAnd(_) => Some(left & right),
+                syn::BinOp::BitXor(_) => Some(left ^ right),
+                _ => None,
             }
         }
-        named
+        _ => None,
     }
 }
 
 fn spanned<T>(spans: &[proc_macro2::Span], value: T) -> st::Spanned<T> {
     st::Spanned {
         bytes: spans
-            .into_iter()
+            .iter()
             .copied()
             .filter_map(|span| {
                 let span = format!("{span:?}");
-                match utils::parse_span(&span) {
-                    Ok(found) => Some(found),
-                    Err(_err) => {
-                        // eprintln!("Failed creating bytes for spanned. {_err}");
-                        None
-                    }
-                }
+                utils::parse_span(&span).ok()
             })
             // first
             .next()
@@ -469,6 +2008,51 @@ pub(crate) struct ParseContext {
     ident: syn::Ident, // name of enum struct
     /// Extras to publish like "Duration"
     publish_builtins: HashMap<String, st::Named<st::ContainerFormat>>,
+    /// Non-literal doc expressions like `include_str!("README.md")`, see [ParseContext::get_doc_comments]
+    doc_includes: Vec<syn::Expr>,
+    /// From `#[codegen(capture_default)]` on the container
+    capture_default: bool,
+    /// Expressions for the default values of the container and its defaulted fields,
+    /// see [st::TypeRoot::captured_defaults]
+    captured_defaults: Vec<TokenStream>,
+    /// Expression for the value of a constant, see [st::TypeRoot::value]
+    value: Option<TokenStream>,
+    /// The type which `Self` refers to, in `impl` blocks
+    self_type: Option<syn::Type>,
+    /// Type parameters in scope of functions, `impl` blocks and traits, see [st::Format::Generic]
+    generic_params: Vec<String>,
+    /// From `#[codegen(result_alias = "io::Result<io::Error>")]` on functions, `impl` blocks and traits,
+    /// with the error type as the only generic argument, see [ParseContext::result_to_formats]
+    result_aliases: Vec<syn::Path>,
+    /// From `#[codegen(extractor = "Cbor = body")]` on functions, `impl` blocks and traits,
+    /// taking precedence over the [ParseContext::frameworks]
+    extractors: Vec<(String, st::ParamSource)>,
+    /// The [FRAMEWORK_EXTRACTORS] from `#[codegen(extractors = "axum")]` on functions, `impl` blocks
+    /// and traits. Without one, types like `Data<T>` are left as they are.
+    frameworks: Vec<&'static [(&'static str, st::ParamSource)]>,
+    /// Problems with `#[codegen(...)]` attributes found by [ParseContext::derive_named],
+    /// reported when the item's tokens are created
+    errors: Vec<syn::Error>,
+}
+
+impl ParseContext {
+    fn new(ident: &Ident, serde_container_ctxt: Option<Ctxt>) -> Self {
+        ParseContext {
+            serde_container_ctxt,
+            ident: ident.clone(),
+            publish_builtins: Default::default(),
+            doc_includes: Vec::new(),
+            capture_default: false,
+            captured_defaults: Vec::new(),
+            value: None,
+            self_type: None,
+            generic_params: Vec::new(),
+            result_aliases: Vec::new(),
+            extractors: Vec::new(),
+            frameworks: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
 }
 
 impl Drop for ParseContext {
@@ -512,11 +2096,276 @@ impl<'a> ParseContext {
         self.type_to_format(field.ty)
     }
 
-    /// Used for fn args
+    /// `attrs` are those of the function, for its `#[codegen(result_alias = "...")]`s
+    pub(crate) fn signature_to_function_format(
+        &mut self,
+        sig: &syn::Signature,
+        attrs: &[syn::Attribute],
+    ) -> Result<st::FunctionFormat> {
+        // type parameters and result aliases of the function are in scope
+        // along with those of its `impl` or trait
+        let outer_generic_params = self.generic_params.len();
+        self.generic_params
+            .extend(type_generic_names(&sig.generics));
+        let outer_result_aliases = self.result_aliases.len();
+        self.result_aliases.extend(codegen_result_aliases(attrs)?);
+        let outer_extractors = self.extractors.len();
+        self.extractors.extend(codegen_extractors(attrs)?);
+        let outer_frameworks = self.frameworks.len();
+        self.frameworks.extend(codegen_frameworks(attrs)?);
+
+        let mut self_opt = None;
+        let mut receiver = None;
+        // the first parameter which is a stream, e.g. `chunks: impl Stream<Item = Chunk>`
+        let mut request_stream = None;
+        let mut extractors = Vec::new();
+        let params = sig
+            .inputs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, arg)| match arg {
+                syn::FnArg::Receiver(recv) => {
+                    let format = self.type_to_format(&recv.ty);
+                    let ident = syn::Ident::new("self", recv.self_token.span);
+                    self_opt = Some(self.derive_named(format, &ident, &recv.attrs, None));
+                    receiver = Some(if recv.colon_token.is_some() {
+                        st::ReceiverKind::Typed
+                    } else if recv.reference.is_none() {
+                        st::ReceiverKind::Owned
+                    } else if recv.mutability.is_some() {
+                        st::ReceiverKind::RefMut
+                    } else {
+                        st::ReceiverKind::Ref
+                    });
+                    None
+                }
+                syn::FnArg::Typed(pat) => {
+                    let named = match self.extractor(&pat.ty) {
+                        Some((extractor, source, inner_ty)) => {
+                            extractors.push(st::Extractor {
+                                param: idx - usize::from(self_opt.is_some()),
+                                extractor,
+                                source,
+                            });
+                            self.pattern_to_named_format(pat, inner_ty, idx)
+                        }
+                        None => self.pattern_to_named_format(pat, &pat.ty, idx),
+                    };
+                    if request_stream.is_none() {
+                        if let Some(item) = stream_item(&pat.ty, &sig.generics) {
+                            request_stream =
+                                Some((named.rust_ident.value.clone(), self.type_to_format(item)));
+                        }
+                    }
+                    Some(named)
+                }
+            })
+            .collect();
+        let mut ret_future_output = None;
+        let mut response_stream = None;
+        let (ret, ret_ok, ret_err) = match &sig.output {
+            syn::ReturnType::Default => (st::Format::Unit, None, None),
+            syn::ReturnType::Type(_, ttype) => {
+                // e.g. `T` for `-> impl Future<Output = T>`, like `async fn f() -> T`
+                let output = match future_output(ttype, &sig.generics) {
+                    Some(output) => {
+                        ret_future_output = Some(Box::new(self.type_to_format(output)));
+                        output
+                    }
+                    None => ttype,
+                };
+                if let Some(item) = stream_item(output, &sig.generics) {
+                    response_stream = Some(self.type_to_format(item));
+                }
+                let (ret_ok, ret_err) = match self.result_to_formats(output) {
+                    Some((ok, err)) => (Some(Box::new(ok)), err.map(Box::new)),
+                    None => (None, None),
+                };
+                (self.type_to_format(ttype), ret_ok, ret_err)
+            }
+        };
+        let call = match (request_stream, response_stream) {
+            (None, None) => st::CallKind::Unary,
+            (None, Some(item)) => st::CallKind::ServerStreaming { item },
+            (Some((param, item)), None) => st::CallKind::ClientStreaming { param, item },
+            (Some((param, request)), Some(response)) => st::CallKind::Bidirectional {
+                param,
+                request,
+                response,
+            },
+        };
+        self.generic_params.truncate(outer_generic_params);
+        self.result_aliases.truncate(outer_result_aliases);
+        self.extractors.truncate(outer_extractors);
+        self.frameworks.truncate(outer_frameworks);
+
+        let mut bounds = Vec::<st::Spanned<(String, Vec<String>)>>::new();
+        let mut lifetimes = Vec::new();
+        for param in &sig.generics.params {
+            match param {
+                syn::GenericParam::Type(typ) => bounds.push(spanned(
+                    &[typ.ident.span()],
+                    (
+                        typ.ident.to_string(),
+                        typ.bounds.iter().map(tokens_to_compact_string).collect(),
+                    ),
+                )),
+                syn::GenericParam::Lifetime(lifetime) => lifetimes.push(spanned(
+                    &[lifetime.lifetime.span()],
+                    lifetime.lifetime.to_string(),
+                )),
+                syn::GenericParam::Const(_) => {}
+            }
+        }
+        let mut where_predicates = Vec::new();
+        for predicate in sig
+            .generics
+            .where_clause
+            .iter()
+            .flat_map(|where_clause| &where_clause.predicates)
+        {
+            // e.g. `T: Clone`, which is added to the bounds of `T`
+            let param_bounds = match predicate {
+                syn::WherePredicate::Type(syn::PredicateType {
+                    lifetimes: None,
+                    bounded_ty: syn::Type::Path(syn::TypePath { qself: None, path }),
+                    bounds: predicate_bounds,
+                    ..
+                }) => path.get_ident().and_then(|ident| {
+                    bounds
+                        .iter_mut()
+                        .find(|param| *ident == param.value.0)
+                        .map(|param| (param, predicate_bounds))
+                }),
+                _ => None,
+            };
+            match param_bounds {
+                Some((param, predicate_bounds)) => param
+                    .value
+                    .1
+                    .extend(predicate_bounds.iter().map(tokens_to_compact_string)),
+                None => where_predicates.push(spanned(
+                    &[predicate.span()],
+                    tokens_to_compact_string(predicate),
+                )),
+            }
+        }
+        bounds.retain(|param| !param.value.1.is_empty());
+
+        Ok(st::FunctionFormat {
+            is_async: sig.asyncness.is_some(),
+            self_opt,
+            receiver,
+            params,
+            ret: Box::new(ret),
+            ret_future_output,
+            ret_ok,
+            ret_err,
+            call,
+            extractors,
+            route: None,
+            bounds,
+            lifetimes,
+            where_predicates,
+        })
+    }
+
+    /// The name, source and inner type of an extractor like `Json<CreateUser>`,
+    /// from [ParseContext::extractors] or the [ParseContext::frameworks]
+    fn extractor<'ty>(
+        &self,
+        ty: &'ty syn::Type,
+    ) -> Option<(st::Spanned<String>, st::ParamSource, &'ty syn::Type)> {
+        let path = match ty {
+            syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+            _ => return None,
+        };
+        let last = path.segments.last()?;
+        let first_type_arg = match &last.arguments {
+            syn::PathArguments::AngleBracketed(args) => {
+                args.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+            }
+            _ => None,
+        };
+        let configured = self
+            .extractors
+            .iter()
+            .rev()
+            .find(|(ident, _)| last.ident == ident)
+            .map(|(_, source)| *source);
+        let source = configured.or_else(|| {
+            self.frameworks
+                .iter()
+                .rev()
+                .flat_map(|extractors| extractors.iter())
+                .find(|(ident, _)| last.ident == ident && first_type_arg.is_some())
+                .map(|(_, source)| *source)
+        })?;
+        let extractor = spanned(&[last.ident.span()], last.ident.to_string());
+        Some((extractor, source, first_type_arg.unwrap_or(ty)))
+    }
+
+    /// The success and error formats of `Result<T, E>`, or of a `Result` alias like `io::Result<T>`
+    /// from [ParseContext::result_aliases]
+    fn result_to_formats(&mut self, ty: &syn::Type) -> Option<(st::Format, Option<st::Format>)> {
+        let path = match ty {
+            syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+            syn::Type::Paren(syn::TypeParen { elem, .. })
+            | syn::Type::Group(syn::TypeGroup { elem, .. }) => return self.result_to_formats(elem),
+            _ => return None,
+        };
+        let last = path.segments.last()?;
+        let args = match &last.arguments {
+            syn::PathArguments::AngleBracketed(args) => args
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        match args.as_slice() {
+            [ok, err] if last.ident == "Result" => {
+                Some((self.type_to_format(ok), Some(self.type_to_format(err))))
+            }
+            [ok] => {
+                // e.g. `Result<T>` after `use std::io::Result` matches `io::Result<io::Error>`
+                let idents = path.segments.iter().map(|segment| &segment.ident);
+                let alias = self.result_aliases.iter().rev().find(|alias| {
+                    let alias_idents = alias.segments.iter().map(|segment| &segment.ident);
+                    idents
+                        .clone()
+                        .rev()
+                        .zip(alias_idents.rev())
+                        .all(|(ident, alias_ident)| ident == alias_ident)
+                })?;
+                let err = match &alias.segments.last()?.arguments {
+                    syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+                        Some(syn::GenericArgument::Type(err)) => Some(err.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                Some((
+                    self.type_to_format(ok),
+                    err.map(|err| self.type_to_format(&err)),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Used for fn args, with `ty` being the type of `pat_type` or of its extractor's contents.
     /// If the argument name isn't present, we'll fallback to the `fallback_name`
     pub(crate) fn pattern_to_named_format(
         &mut self,
         pat_type: &syn::PatType,
+        ty: &syn::Type,
         arg_position: usize,
     ) -> st::Named<st::Format> {
         let arg_ident = self
@@ -524,7 +2373,7 @@ impl<'a> ParseContext {
             .unwrap_or_else(|| {
                 syn::Ident::new(&format!("arg{arg_position}"), pat_type.colon_token.span)
             });
-        let format = self.type_to_format(&pat_type.ty);
+        let format = self.type_to_format(ty);
 
         self.derive_named(format, &arg_ident, &pat_type.attrs, None)
     }
@@ -534,6 +2383,10 @@ impl<'a> ParseContext {
             syn::Pat::Ident(ident) => Some(ident.ident.clone()),
             syn::Pat::Reference(val) => self.pattern_binding_to_ident(&val.pat),
             syn::Pat::Type(asc) => self.pattern_binding_to_ident(&asc.pat),
+            // e.g. `Json(body)` for an extractor
+            syn::Pat::TupleStruct(tuple) if tuple.elems.len() == 1 => {
+                self.pattern_binding_to_ident(&tuple.elems[0])
+            }
             _ => None,
         }
     }
@@ -565,7 +2418,22 @@ impl<'a> ParseContext {
                 let elems = elems.iter().map(|t| self.type_to_format(t));
                 st::Format::Tuple(elems.collect())
             }
-            SynType::Path(TypePath { path, .. }) => match last_path_element(&path) {
+            SynType::Path(TypePath { qself: None, path })
+                if path.get_ident().is_some_and(|ident| {
+                    self.generic_params.iter().any(|param| ident == param)
+                }) =>
+            {
+                st::Format::Generic {
+                    ident: path_to_compact_string(path),
+                }
+            }
+            SynType::Path(TypePath { path, .. })
+                if path.is_ident("Self") && self.self_type.is_some() =>
+            {
+                let self_type = self.self_type.clone().expect("checked");
+                self.type_to_format(&self_type)
+            }
+            SynType::Path(TypePath { path, .. }) => match last_path_element(path) {
                 Some(ref ts) => self.generic_to_format(ts),
                 _ => st::Format::Incomplete {
                     debug: format!("Unknown type path: {path:?}"),
@@ -597,7 +2465,7 @@ impl<'a> ParseContext {
             #[allow(unknown_lints)]
             #[cfg_attr(test, deny(non_exhaustive_omitted_patterns))]
             _ => st::Format::Incomplete {
-                debug: format!("Unknown other type"),
+                debug: "Unknown other type".to_string(),
             },
         }
     }
@@ -627,7 +2495,7 @@ impl<'a> ParseContext {
             "Box" | "Cow" | "Rc" | "Arc" | "Cell" | "RefCell" if ts.args.len() == 1 => {
                 to_format(&ts.args[0])
             }
-            "Duration" => self.add_builtin::<std::time::Duration>(
+            "Duration" => self.add_builtin(
                 "Duration",
                 r#"
 A `Duration` type to represent a span of time, typically used for system
@@ -685,7 +2553,7 @@ crate to do so.
                     )
                 },
             ),
-            "SystemTime" => self.add_builtin::<std::time::SystemTime>(
+            "SystemTime" => self.add_builtin(
                 "SystemTime",
                 r#"A measurement of the system clock, useful for talking to 
 external entities like the file system or other processes."#,
@@ -731,7 +2599,7 @@ external entities like the file system or other processes."#,
                 let origin = utils::parse_span(ts.ident.span()).ok();
                 let ok = to_format(&ts.args[0]);
                 let err = to_format(&ts.args[1]);
-                self.add_builtin::<std::result::Result<(), ()>>(
+                self.add_builtin(
                     &format!("Result_Ok{}_Err{}", ok.as_ident(), err.as_ident()),
                     r#"`Result` is a type that represents either success ([`Ok`]) or failure ([`Err`])."#,
                     origin,
@@ -776,7 +2644,7 @@ external entities like the file system or other processes."#,
         }
     }
 
-    fn add_builtin<T: std::any::Any>(
+    fn add_builtin(
         &mut self,
         name: &str,
         docs: &str,
@@ -804,13 +2672,45 @@ external entities like the file system or other processes."#,
     }
 
     fn derive_named_field(&mut self, field: &ast::Field<'a>) -> st::Named<st::Format> {
-        match &field.member {
+        let (mut named, ident_span) = match &field.member {
             syn::Member::Named(named) => {
                 let format = self.field_to_format(field);
-                self.derive_named(format, &named, &field.original.attrs, None)
+                (
+                    self.derive_named(format, named, &field.original.attrs, None),
+                    named.span(),
+                )
+            }
+            syn::Member::Unnamed(index) => {
+                // serde's unnamed members are spanned at the call site, so point at the type instead
+                let format = self.field_to_format(field);
+                (
+                    self.derive_named_at(
+                        format,
+                        index.index.to_string(),
+                        field.ty.span(),
+                        &field.original.attrs,
+                        None,
+                    ),
+                    field.ty.span(),
+                )
+            }
+        };
+        named.rust_attrs.visibility = Some(visibility_to_string(&field.original.vis, ident_span));
+        if self.capture_default {
+            let default = match field.attrs.default() {
+                attr::Default::None => None,
+                attr::Default::Default => {
+                    let ty = field.ty;
+                    Some(quote!(<#ty as ::core::default::Default>::default()))
+                }
+                attr::Default::Path(path) => Some(quote!(#path())),
+            };
+            if let Some(default) = default {
+                named.captured_default = Some(self.captured_defaults.len());
+                self.captured_defaults.push(default);
             }
-            syn::Member::Unnamed(_) => todo!("unnamed field"),
         }
+        named
     }
 
     fn derive_named_fields(
@@ -826,20 +2726,6 @@ external entities like the file system or other processes."#,
     ) -> impl Iterator<Item = st::Named<st::Format>> + 'a {
         fields.iter().map(move |f| self.derive_named_field(f))
     }
-
-    fn derive_fields_tuple(
-        &'a mut self,
-        fields: &'a [&'a ast::Field<'a>],
-    ) -> impl Iterator<Item = st::Format> + 'a {
-        fields.iter().map(move |f| self.field_to_format(f))
-    }
-
-    fn derive_fields_tuple_alt(
-        &'a mut self,
-        fields: &'a [ast::Field<'a>],
-    ) -> impl Iterator<Item = st::Format> + 'a {
-        fields.iter().map(move |f| self.field_to_format(f))
-    }
 }
 
 struct TypeFormat {
@@ -158,8 +158,8 @@ function convert(input: gen.Input): GeneratedDecl[] {
                 variant
               )}]);`;
             },
-            Tuple(formats) {
-              const formatGoList = tupleFormats(createFormat, formats);
+            Tuple({ fields }) {
+              const formatGoList = tupleFormats(createFormat, fields.map((field) => field.format));
               const vnStr = namedStr(variant);
               const innerTypeRef = `[${formatGoList
                 .map((f) => f.fmt.src)
@@ -305,8 +305,8 @@ function convert(input: gen.Input): GeneratedDecl[] {
         }
         $decl.add`}`;
       },
-      TupleStruct(formats) {
-        const formatTsList = tupleFormats(createFormat, formats);
+      TupleStruct({ fields }) {
+        const formatTsList = tupleFormats(createFormat, fields.map((field) => field.format));
         // type
         $decl.lines.push(...docs);
         $decl.add`export type ${goDeclIdent} = [${formatTsList
@@ -427,6 +427,7 @@ const createFormatter =
   (forIdent) => {
     const createFormat: Formatter = gen.Format.apply({
       TypeName: (value) => ({ src: ident(value.ident) }),
+      Generic: (value) => ({ src: ident(value.ident) }),
       I8: always("int8"),
       I16: always("int16"),
       I32: always("int32"),
@@ -114,6 +114,11 @@ export namespace Format {
     Incomplete(inner: Incomplete["Incomplete"]): R,
     /** The name of a container. */
     TypeName(inner: TypeName["TypeName"]): R,
+    /**
+     * A type parameter of a function, `impl` block or trait, e.g. `T` in `fn list<T>() -> Vec<T>`.
+     * Type parameters of containers are left as [Format::TypeName]s.
+     */
+    Generic(inner: Generic["Generic"]): R,
     Unit(): R,
     Bool(): R,
     I8(): R,
@@ -179,6 +184,7 @@ export namespace Format {
       if (typeof input !== "object" || input == null) throw new TypeError("Unexpected non-object for input");
       if ("Incomplete" in input) return to.Incomplete(input["Incomplete"]);
       if ("TypeName" in input) return to.TypeName(input["TypeName"]);
+      if ("Generic" in input) return to.Generic(input["Generic"]);
       if ("Option" in input) return to.Option(input["Option"]);
       if ("Seq" in input) return to.Seq(input["Seq"]);
       if ("Map" in input) return to.Map(input["Map"]);
@@ -215,6 +221,22 @@ export namespace Format {
   export function TypeName(value: TypeName["TypeName"]): TypeName {
     return { TypeName: value }
   }
+  /**
+   * A type parameter of a function, `impl` block or trait, e.g. `T` in `fn list<T>() -> Vec<T>`.
+   * Type parameters of containers are left as [Format::TypeName]s.
+   */
+  export type Generic = {
+    Generic: {
+      ident: string;
+    };
+  };
+  /**
+   * A type parameter of a function, `impl` block or trait, e.g. `T` in `fn list<T>() -> Vec<T>`.
+   * Type parameters of containers are left as [Format::TypeName]s.
+   */
+  export function Generic(value: Generic["Generic"]): Generic {
+    return { Generic: value }
+  }
   export type Unit = "Unit"
   export function Unit(): Unit {
     return "Unit";
@@ -368,6 +390,7 @@ export namespace Format {
 export type Format =
   | Format.Incomplete
   | Format.TypeName
+  | Format.Generic
   | Format.Unit
   | Format.Bool
   | Format.I8
@@ -459,11 +482,13 @@ export namespace ContainerFormat {
   /** A struct with several unnamed parameters, e.g. `struct A(u16, u32)` */
   export type TupleStruct = {
     /** A struct with several unnamed parameters, e.g. `struct A(u16, u32)` */
-    TupleStruct: Array<Format>
+    TupleStruct: {
+      fields: Array<NamedField>;
+    };
   };
   /** A struct with several unnamed parameters, e.g. `struct A(u16, u32)` */
-  export function TupleStruct(value: Array<Format>): TupleStruct {
-    return { TupleStruct: value };
+  export function TupleStruct(value: TupleStruct["TupleStruct"]): TupleStruct {
+    return { TupleStruct: value }
   }
   /** A struct with named parameters, e.g. `struct A { a: Foo }`. */
   export type Struct = {
@@ -617,11 +642,13 @@ export namespace VariantFormat {
   /** A struct with several unnamed parameters, e.g. `A` in `enum X { A(u16, u32) }` */
   export type Tuple = {
     /** A struct with several unnamed parameters, e.g. `A` in `enum X { A(u16, u32) }` */
-    Tuple: Array<Format>
+    Tuple: {
+      fields: Array<NamedField>;
+    };
   };
   /** A struct with several unnamed parameters, e.g. `A` in `enum X { A(u16, u32) }` */
-  export function Tuple(value: Array<Format>): Tuple {
-    return { Tuple: value };
+  export function Tuple(value: Tuple["Tuple"]): Tuple {
+    return { Tuple: value }
   }
   /** A struct with named parameters, e.g. `A` in `enum X { A { a: Foo } }` */
   export type Struct = {